@@ -0,0 +1,437 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+
+use crate::GrowthPolicy;
+
+// Bytes per key whose bits get flipped for the avalanche test
+const MAX_AVALANCHE_BYTES: usize = 64;
+
+/// What `analyze` measures against.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Options {
+    // Bucket count to spread the keys over, by default the fewest a table with
+    // `policy` needs for them
+    pub buckets: Option<usize>,
+    // Index mode and power of two rounding
+    pub policy: GrowthPolicy,
+    // Keys the avalanche test runs on; every bit of each gets flipped
+    pub avalanche_keys: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            buckets: None,
+            policy: GrowthPolicy::default(),
+            avalanche_keys: 1000,
+        }
+    }
+}
+
+/// How well a hasher spread one key set over a bucket array.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub keys: usize,
+    pub buckets: usize,
+    // Pearson's chi-square of the bucket counts against a uniform spread, and the
+    // same as a z-score: |z| beyond 3 or so means the spread isn't uniform
+    pub chi_square: f64,
+    pub degrees_of_freedom: usize,
+    pub z_score: f64,
+    // Keys whose full 64-bit hash an earlier key already had
+    pub hash_collisions: usize,
+    // Keys landing in a bucket an earlier key already occupied
+    pub bucket_collisions: usize,
+    pub max_bucket_len: usize,
+    // Single input bit flips tried, and the share of output bits each one flipped
+    // on average (ideally 0.5)
+    pub avalanche_trials: u64,
+    pub avalanche_mean: f64,
+    // Worst output bit's |2 * flip probability - 1|, 0 is ideal
+    pub avalanche_bias: f64,
+    // Worst |correlation| between two output bits flipping together, 0 is ideal
+    pub bit_independence: f64,
+}
+
+/// Hashes `keys` with `hash_builder` and measures the bucket distribution and the
+/// avalanche behaviour of the hash function on them.
+pub fn analyze<K, S>(keys: &[K], hash_builder: &S, options: &Options) -> Report
+where
+    K: Hash,
+    S: BuildHasher,
+{
+    options.policy.validate();
+
+    let buckets = options
+        .buckets
+        .unwrap_or_else(|| options.policy.min_len(keys.len()))
+        .max(1);
+    if options.policy.power_of_two {
+        assert!(
+            buckets.is_power_of_two(),
+            "bucket count must be a power of two"
+        );
+    }
+
+    let mut counts = vec![0usize; buckets];
+    let mut hashes = HashSet::with_capacity(keys.len());
+    let mut hash_collisions = 0;
+
+    for key in keys {
+        let hash = hash_builder.hash_one(key);
+        counts[options.policy.index.bucket_index(hash, buckets)] += 1;
+
+        if !hashes.insert(hash) {
+            hash_collisions += 1;
+        }
+    }
+
+    // Every bucket expects keys / buckets of them
+    let expected = keys.len() as f64 / buckets as f64;
+    let chi_square = match keys.is_empty() {
+        true => 0.0,
+        false => counts
+            .iter()
+            .map(|&count| (count as f64 - expected).powi(2) / expected)
+            .sum(),
+    };
+    let degrees_of_freedom = buckets - 1;
+    let z_score = match degrees_of_freedom {
+        0 => 0.0,
+        df => (chi_square - df as f64) / (2.0 * df as f64).sqrt(),
+    };
+
+    let occupied = counts.iter().filter(|&&count| count > 0).count();
+    let avalanche = avalanche(
+        &keys[..keys.len().min(options.avalanche_keys)],
+        hash_builder,
+    );
+
+    Report {
+        keys: keys.len(),
+        buckets,
+        chi_square,
+        degrees_of_freedom,
+        z_score,
+        hash_collisions,
+        bucket_collisions: keys.len() - occupied,
+        max_bucket_len: counts.iter().copied().max().unwrap_or(0),
+        avalanche_trials: avalanche.trials,
+        avalanche_mean: avalanche.mean(),
+        avalanche_bias: avalanche.bias(),
+        bit_independence: avalanche.bit_independence(),
+    }
+}
+
+// Collects the bytes a key feeds its hasher, one chunk per `write`, so single bits of
+// them can be flipped and the writes replayed as the key made them
+#[derive(Default)]
+struct ByteRecorder(Vec<Vec<u8>>);
+
+impl Hasher for ByteRecorder {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.push(bytes.to_vec());
+    }
+
+    fn finish(&self) -> u64 {
+        0
+    }
+}
+
+// Output bit flip counts over all trials, alone and in pairs
+struct Avalanche {
+    trials: u64,
+    flips: [u64; 64],
+    // [j][k] for j < k
+    joint_flips: Vec<[u64; 64]>,
+}
+
+fn avalanche<K: Hash, S: BuildHasher>(keys: &[K], hash_builder: &S) -> Avalanche {
+    let mut avalanche = Avalanche {
+        trials: 0,
+        flips: [0; 64],
+        joint_flips: vec![[0; 64]; 64],
+    };
+
+    for key in keys {
+        let mut chunks = record_writes(key);
+        let hash = replay_writes(hash_builder, &chunks);
+
+        // (chunk, byte) of the key's first bytes, across however many writes
+        let bytes: Vec<_> = chunks
+            .iter()
+            .enumerate()
+            .flat_map(|(chunk, bytes)| (0..bytes.len()).map(move |byte| (chunk, byte)))
+            .take(MAX_AVALANCHE_BYTES)
+            .collect();
+
+        for (chunk, byte) in bytes {
+            for bit in 0..8 {
+                chunks[chunk][byte] ^= 1 << bit;
+                avalanche.record(hash ^ replay_writes(hash_builder, &chunks));
+                chunks[chunk][byte] ^= 1 << bit;
+            }
+        }
+    }
+
+    avalanche
+}
+
+fn record_writes<K: Hash + ?Sized>(key: &K) -> Vec<Vec<u8>> {
+    let mut recorder = ByteRecorder::default();
+    key.hash(&mut recorder);
+    recorder.0
+}
+
+// Hashes recorded chunks with the same `write` calls they were recorded from. Integer
+// writes come back as byte writes, which the hashers here treat the same.
+fn replay_writes<S: BuildHasher>(hash_builder: &S, chunks: &[Vec<u8>]) -> u64 {
+    let mut hasher = hash_builder.build_hasher();
+    for chunk in chunks {
+        hasher.write(chunk);
+    }
+    hasher.finish()
+}
+
+impl Avalanche {
+    fn record(&mut self, diff: u64) {
+        self.trials += 1;
+
+        for j in set_bits(diff) {
+            self.flips[j] += 1;
+            let above = u64::MAX.checked_shl(j as u32 + 1).unwrap_or(0);
+            for k in set_bits(diff & above) {
+                self.joint_flips[j][k] += 1;
+            }
+        }
+    }
+
+    fn mean(&self) -> f64 {
+        if self.trials == 0 {
+            return f64::NAN;
+        }
+
+        self.flips.iter().sum::<u64>() as f64 / (64 * self.trials) as f64
+    }
+
+    fn bias(&self) -> f64 {
+        if self.trials == 0 {
+            return f64::NAN;
+        }
+
+        self.flips
+            .iter()
+            .map(|&flips| (2.0 * flips as f64 / self.trials as f64 - 1.0).abs())
+            .fold(0.0, f64::max)
+    }
+
+    // Phi coefficient of every pair of output bits. A bit that always or never flips
+    // counts as fully dependent.
+    fn bit_independence(&self) -> f64 {
+        if self.trials == 0 {
+            return f64::NAN;
+        }
+
+        let n = self.trials as f64;
+        let mut worst: f64 = 0.0;
+
+        for j in 0..64 {
+            for k in j + 1..64 {
+                let (a, b) = (self.flips[j] as f64, self.flips[k] as f64);
+                let both = self.joint_flips[j][k] as f64;
+                let variance = a * (n - a) * b * (n - b);
+
+                let phi = match variance > 0.0 {
+                    true => (both * n - a * b) / variance.sqrt(),
+                    false => 1.0,
+                };
+                worst = worst.max(phi.abs());
+            }
+        }
+
+        worst
+    }
+}
+
+fn set_bits(mut bits: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        let bit = bits.trailing_zeros() as usize;
+        bits &= bits.wrapping_sub(1);
+        (bit < 64).then_some(bit)
+    })
+}
+
+impl Report {
+    pub fn to_json(&self) -> String {
+        // JSON has no NaN, which the avalanche figures are without any trials
+        let number = |value: f64| match value.is_finite() {
+            true => value.to_string(),
+            false => "null".to_string(),
+        };
+
+        format!(
+            concat!(
+                "{{\"keys\":{},\"buckets\":{},\"chi_square\":{},\"degrees_of_freedom\":{},",
+                "\"z_score\":{},\"hash_collisions\":{},\"bucket_collisions\":{},",
+                "\"max_bucket_len\":{},\"avalanche_trials\":{},\"avalanche_mean\":{},",
+                "\"avalanche_bias\":{},\"bit_independence\":{}}}"
+            ),
+            self.keys,
+            self.buckets,
+            number(self.chi_square),
+            self.degrees_of_freedom,
+            number(self.z_score),
+            self.hash_collisions,
+            self.bucket_collisions,
+            self.max_bucket_len,
+            self.avalanche_trials,
+            number(self.avalanche_mean),
+            number(self.avalanche_bias),
+            number(self.bit_independence),
+        )
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "keys:               {}", self.keys)?;
+        writeln!(f, "buckets:            {}", self.buckets)?;
+        writeln!(
+            f,
+            "chi-square:         {:.2} ({} degrees of freedom, z = {:.2})",
+            self.chi_square, self.degrees_of_freedom, self.z_score
+        )?;
+        writeln!(f, "hash collisions:    {}", self.hash_collisions)?;
+        writeln!(f, "bucket collisions:  {}", self.bucket_collisions)?;
+        writeln!(f, "max bucket length:  {}", self.max_bucket_len)?;
+        if self.avalanche_trials == 0 {
+            writeln!(f, "avalanche:          not measured")?;
+            return write!(f, "bit independence:   not measured");
+        }
+
+        writeln!(
+            f,
+            "avalanche:          {:.4} of output bits flipped on average, worst bit bias {:.4} ({} trials)",
+            self.avalanche_mean, self.avalanche_bias, self.avalanche_trials
+        )?;
+        write!(
+            f,
+            "bit independence:   worst correlation {:.4}",
+            self.bit_independence
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::RandomState;
+
+    use super::*;
+    use crate::hashers::{FxBuildHasher, WyBuildHasher};
+    use crate::IndexMode;
+
+    #[test]
+    fn test_uniform_hasher() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let options = Options {
+            avalanche_keys: 100,
+            ..Options::default()
+        };
+        let report = analyze(&keys, &RandomState::new(), &options);
+
+        assert_eq!(report.buckets, 13_334);
+        assert!(report.z_score.abs() < 5.0, "{}", report);
+        assert!(report.max_bucket_len <= 10, "{}", report);
+        assert_eq!(report.hash_collisions, 0);
+
+        // 100 keys of 8 bytes
+        assert_eq!(report.avalanche_trials, 6400);
+        assert!((report.avalanche_mean - 0.5).abs() < 0.01, "{}", report);
+        assert!(report.avalanche_bias < 0.15, "{}", report);
+        assert!(report.bit_independence < 0.1, "{}", report);
+    }
+
+    #[test]
+    fn test_weak_hasher() {
+        // Under FxHash these keys' hashes have zero low bits (see `FxHasher`), so a
+        // power of two modulo piles them all into bucket 0
+        let keys: Vec<u64> = (0..1000).map(|i| i << 32).collect();
+        let options = Options {
+            buckets: Some(1024),
+            policy: GrowthPolicy {
+                power_of_two: true,
+                ..GrowthPolicy::default()
+            },
+            avalanche_keys: 10,
+        };
+        let report = analyze(&keys, &FxBuildHasher::default(), &options);
+
+        assert_eq!(report.max_bucket_len, 1000);
+        assert_eq!(report.bucket_collisions, 999);
+        assert!(report.z_score > 100.0, "{}", report);
+        // A multiply only carries upwards: low output bits hardly ever flip, and
+        // when they do, the bits above them flip too
+        assert!(report.avalanche_bias > 0.9, "{}", report);
+        assert!(report.bit_independence > 0.5, "{}", report);
+
+        // Multiply-shift reads the high bits and spreads the same hashes
+        let report = analyze(
+            &keys,
+            &FxBuildHasher::default(),
+            &Options {
+                policy: GrowthPolicy {
+                    index: IndexMode::MultiplyShift,
+                    ..options.policy
+                },
+                ..options
+            },
+        );
+        assert!(report.max_bucket_len <= 4, "{}", report);
+    }
+
+    #[test]
+    fn test_replays_writes() {
+        let keys = ["", "a", "account_7", "a key longer than a single word"].map(String::from);
+
+        for key in &keys {
+            // The string's bytes, then a 0xff terminator
+            let chunks = record_writes(key);
+            assert_eq!(chunks.len(), 2);
+
+            let fx = FxBuildHasher::default();
+            assert_eq!(replay_writes(&fx, &chunks), fx.hash_one(key));
+            let wy = WyBuildHasher::new();
+            assert_eq!(replay_writes(&wy, &chunks), wy.hash_one(key));
+        }
+
+        // Integer keys too
+        let fx = FxBuildHasher::default();
+        assert_eq!(
+            replay_writes(&fx, &record_writes(&42u64)),
+            fx.hash_one(42u64)
+        );
+    }
+
+    #[test]
+    fn test_collisions_and_json() {
+        let keys = ["a", "b", "a", "c"];
+        let report = analyze(&keys, &RandomState::new(), &Options::default());
+
+        assert_eq!(report.hash_collisions, 1);
+        assert!(report.bucket_collisions >= 1);
+
+        let json = report.to_json();
+        assert!(json.starts_with("{\"keys\":4,\"buckets\":6,"), "{}", json);
+        assert!(json.contains("\"hash_collisions\":1,"), "{}", json);
+
+        // No bits to flip: avalanche figures are undefined
+        let report = analyze(&[()], &RandomState::new(), &Options::default());
+        assert!(report
+            .to_json()
+            .ends_with("\"avalanche_bias\":null,\"bit_independence\":null}"));
+        assert!(report
+            .to_string()
+            .ends_with("bit independence:   not measured"));
+    }
+}
@@ -0,0 +1,144 @@
+// Reports how evenly a hasher spreads a key set over a bucket array.
+//
+//     hash_analysis [--hasher NAME] [--seed N] [--buckets N] [--index MODE]
+//                   [--avalanche-keys N] [--json] [FILE]
+//
+// Keys are the lines of FILE, or of stdin without one. Hashers: default (std's
+// RandomState), sip, fx, wy, xxh3, stable. Index modes: modulo, multiply-shift,
+// fastrange.
+//
+// `stable` reports no avalanche figures: `Stable` keys hand the hasher their
+// finished stable hash, so flipping the bits it's fed would test SipHash on a
+// mixed `u64` rather than on the key.
+
+use std::collections::hash_map::RandomState;
+use std::io::{self, BufRead, BufReader};
+use std::process::ExitCode;
+use std::{env, fs};
+
+use hash_table::analysis::{analyze, Options, Report};
+use hash_table::hashers::{FxBuildHasher, SipBuildHasher, WyBuildHasher, Xxh3BuildHasher};
+use hash_table::stable::{Stable, StableBuildHasher};
+use hash_table::{GrowthPolicy, IndexMode};
+
+struct Args {
+    hasher: String,
+    // Seed or SipHash key, fixed so reports can be reproduced
+    seed: u64,
+    options: Options,
+    json: bool,
+    path: Option<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        hasher: "default".to_string(),
+        seed: 0,
+        options: Options::default(),
+        json: false,
+        path: None,
+    };
+    let mut argv = env::args().skip(1);
+
+    while let Some(arg) = argv.next() {
+        let mut value = |name: &str| argv.next().ok_or(format!("{} needs a value", name));
+
+        match arg.as_str() {
+            "--hasher" => args.hasher = value("--hasher")?,
+            "--seed" => args.seed = parse_number(&value("--seed")?)?,
+            "--buckets" => args.options.buckets = Some(parse_number(&value("--buckets")?)?),
+            "--avalanche-keys" => {
+                args.options.avalanche_keys = parse_number(&value("--avalanche-keys")?)?
+            }
+            "--index" => {
+                let index = match value("--index")?.as_str() {
+                    "modulo" => IndexMode::Modulo,
+                    "multiply-shift" => IndexMode::MultiplyShift,
+                    "fastrange" => IndexMode::FastRange,
+                    other => return Err(format!("unknown index mode {}", other)),
+                };
+                args.options.policy = GrowthPolicy {
+                    power_of_two: index == IndexMode::MultiplyShift,
+                    index,
+                    ..GrowthPolicy::default()
+                };
+            }
+            "--json" => args.json = true,
+            path if !path.starts_with("--") && args.path.is_none() => {
+                args.path = Some(path.to_string())
+            }
+            other => return Err(format!("unexpected argument {}", other)),
+        }
+    }
+
+    if args.options.policy.power_of_two {
+        if let Some(buckets) = args.options.buckets {
+            if !buckets.is_power_of_two() {
+                return Err("multiply-shift needs a power of two bucket count".to_string());
+            }
+        }
+    }
+
+    Ok(args)
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} isn't a valid number", value))
+}
+
+fn read_keys(path: Option<&str>) -> io::Result<Vec<String>> {
+    let reader: Box<dyn BufRead> = match path {
+        Some(path) => Box::new(BufReader::new(fs::File::open(path)?)),
+        None => Box::new(io::stdin().lock()),
+    };
+
+    reader.lines().collect()
+}
+
+fn run(args: &Args, keys: &[String]) -> Result<Report, String> {
+    let options = &args.options;
+
+    Ok(match args.hasher.as_str() {
+        "default" => analyze(keys, &RandomState::new(), options),
+        "sip" => analyze(
+            keys,
+            &SipBuildHasher::with_keys(args.seed, args.seed),
+            options,
+        ),
+        "fx" => analyze(keys, &FxBuildHasher::default(), options),
+        "wy" => analyze(keys, &WyBuildHasher::with_seed(args.seed), options),
+        "xxh3" => analyze(keys, &Xxh3BuildHasher::with_seed(args.seed), options),
+        "stable" => {
+            let keys: Vec<_> = keys.iter().map(Stable).collect();
+            let options = Options {
+                avalanche_keys: 0,
+                ..*options
+            };
+            analyze(&keys, &StableBuildHasher, &options)
+        }
+        other => return Err(format!("unknown hasher {}", other)),
+    })
+}
+
+fn main() -> ExitCode {
+    let result = parse_args().and_then(|args| {
+        let keys = read_keys(args.path.as_deref()).map_err(|err| err.to_string())?;
+        let report = run(&args, &keys)?;
+
+        match args.json {
+            true => println!("{}", report.to_json()),
+            false => println!("hasher:             {}\n{}", args.hasher, report),
+        }
+        Ok(())
+    });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("hash_analysis: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,33 @@
+/// What a `HashTable` keeps next to every entry besides its key and value.
+///
+/// `()` keeps nothing, so a resize hashes every key again. `u64` keeps the key's
+/// full hash: resizes never call the hasher and lookups skip `Eq` for entries whose
+/// hash differs, at 8 more bytes per entry.
+pub trait HashCache: Copy {
+    fn store(hash: u64) -> Self;
+
+    fn load(self) -> Option<u64>;
+
+    // Whether an entry carrying this cache may hold a key hashing to `hash`
+    fn matches(self, hash: u64) -> bool {
+        self.load().is_none_or(|cached| cached == hash)
+    }
+}
+
+impl HashCache for () {
+    fn store(_hash: u64) -> Self {}
+
+    fn load(self) -> Option<u64> {
+        None
+    }
+}
+
+impl HashCache for u64 {
+    fn store(hash: u64) -> Self {
+        hash
+    }
+
+    fn load(self) -> Option<u64> {
+        Some(self)
+    }
+}
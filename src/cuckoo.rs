@@ -0,0 +1,313 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::mem;
+
+use crate::KV;
+
+// Two candidate slots per key; above half full insertions start failing often
+const MAX_LOAD_FACTOR: f64 = 0.5;
+
+// Eviction path length after which the insert is considered stuck in a cycle
+const MAX_KICKS: usize = 64;
+
+// Attempts with fresh seeds at the same size before the table is grown instead
+const MAX_REHASHES: usize = 4;
+
+/// Cuckoo hashing: every key has exactly one candidate slot in each of the two
+/// tables, each indexed by its own independently seeded hash function. Lookups and
+/// deletes probe at most two slots. An insert into an occupied slot kicks the
+/// resident out to its alternative slot, and so on along the eviction path.
+#[derive(Clone, Debug)]
+pub struct CuckooTable<K, V, S = RandomState> {
+    tables: [Vec<Option<KV<K, V>>>; 2],
+    // Fed to the hasher ahead of the key, so the one builder gives a hash function
+    // per table. A rehash moves on to the next pair.
+    seeds: [u64; 2],
+    size: usize,
+    hash_builder: S,
+}
+
+impl<K, V> CuckooTable<K, V, RandomState>
+where
+    K: Hash + Eq,
+{
+    pub fn new(with_capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(with_capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> CuckooTable<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    pub fn with_capacity_and_hasher(with_capacity: usize, hash_builder: S) -> Self {
+        let len = ((with_capacity as f64 / MAX_LOAD_FACTOR / 2.0).ceil() as usize).max(1);
+
+        CuckooTable {
+            tables: [Self::empty_slots(len), Self::empty_slots(len)],
+            seeds: [0, 1],
+            size: 0,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some((table, index)) = self.find(&key) {
+            let kv = self.tables[table][index]
+                .as_mut()
+                .expect("found slot is occupied");
+            return Some(mem::replace(&mut kv.value, value));
+        }
+
+        let len = self.tables[0].len();
+        if (self.size + 1) as f64 > (2 * len) as f64 * MAX_LOAD_FACTOR {
+            self.rehash(len * 2, Vec::new());
+        }
+
+        self.size += 1;
+        if let Err(homeless) = self.place(KV::new(key, value)) {
+            self.rehash(self.tables[0].len(), vec![homeless]);
+        }
+
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (table, index) = self.find(key)?;
+        self.tables[table][index].as_ref().map(|kv| &kv.value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (table, index) = self.find(key)?;
+        self.tables[table][index].as_mut().map(|kv| &mut kv.value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    pub fn delete<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (table, index) = self.find(key)?;
+        self.size -= 1;
+        self.tables[table][index].take().map(|kv| kv.value)
+    }
+
+    // Worst case two probes, no matter how full the table is
+    fn find<Q>(&self, key: &Q) -> Option<(usize, usize)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        (0..2)
+            .map(|table| (table, self.create_index(table, key)))
+            .find(|&(table, index)| {
+                self.tables[table][index]
+                    .as_ref()
+                    .is_some_and(|kv| kv.key.borrow() == key)
+            })
+    }
+
+    fn create_index<Q: Hash + ?Sized>(&self, table: usize, key: &Q) -> usize {
+        let mut hasher = self.hash_builder.build_hasher();
+        hasher.write_u64(self.seeds[table]);
+        key.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        // Modulo arithmetic -> Uniform Distribution
+        (hash % (self.tables[table].len() as u64)) as usize
+    }
+
+    // Walks the eviction path. Gives back the entry left without a slot when the
+    // path gets too long, which in practice means it runs in a cycle.
+    fn place(&mut self, mut kv: KV<K, V>) -> Result<(), KV<K, V>> {
+        for table in 0..2 {
+            let index = self.create_index(table, &kv.key);
+            if self.tables[table][index].is_none() {
+                self.tables[table][index] = Some(kv);
+                return Ok(());
+            }
+        }
+
+        for kick in 0..MAX_KICKS {
+            let table = kick % 2;
+            let index = self.create_index(table, &kv.key);
+
+            match self.tables[table][index].replace(kv) {
+                None => return Ok(()),
+                Some(evicted) => kv = evicted,
+            }
+        }
+
+        Err(kv)
+    }
+
+    // Rebuilds the table with new seeds until every entry finds a slot, growing
+    // it if new seeds alone keep failing
+    fn rehash(&mut self, mut len: usize, mut pending: Vec<KV<K, V>>) {
+        let mut attempts = 0;
+
+        loop {
+            for table in &mut self.tables {
+                let old_slots = mem::replace(table, Self::empty_slots(len));
+                pending.extend(old_slots.into_iter().flatten());
+            }
+            self.seeds = self.seeds.map(|seed| seed + 2);
+
+            let mut homeless = None;
+            while let Some(kv) = pending.pop() {
+                if let Err(kv) = self.place(kv) {
+                    homeless = Some(kv);
+                    break;
+                }
+            }
+
+            match homeless {
+                None => return,
+                Some(kv) => pending.push(kv),
+            }
+
+            attempts += 1;
+            if attempts % MAX_REHASHES == 0 {
+                len *= 2;
+            }
+        }
+    }
+
+    fn empty_slots(len: usize) -> Vec<Option<KV<K, V>>> {
+        (0..len).map(|_| None).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashers::FxBuildHasher;
+
+    #[test]
+    fn test_insert_get() {
+        let mut hash_table = CuckooTable::new(1);
+
+        for i in 0..2000 {
+            assert_eq!(hash_table.insert(format!("key_{}", i), i), None);
+        }
+
+        assert_eq!(hash_table.size(), 2000);
+        assert_eq!(hash_table.insert("key_3".to_string(), 300), Some(3));
+        assert_eq!(hash_table.size(), 2000);
+
+        for i in 0..2000 {
+            let expected = if i == 3 { 300 } else { i };
+            assert_eq!(hash_table.get(&format!("key_{}", i)), Some(&expected));
+        }
+    }
+
+    #[test]
+    fn test_with_hasher() {
+        // A deterministic builder still gives two hash functions, one per seed
+        let mut hash_table = CuckooTable::with_hasher(FxBuildHasher::default());
+
+        for i in 0..1000 {
+            hash_table.insert(format!("key_{}", i), i);
+        }
+
+        // Keys kicked out to their slot in the second table are found through `&str`
+        // just the same
+        let kicked = (0..1000)
+            .filter(|i| {
+                let (table, _) = hash_table.find(format!("key_{}", i).as_str()).unwrap();
+                table == 1
+            })
+            .count();
+        assert!(kicked > 0);
+
+        *hash_table.get_mut("key_7").unwrap() = 700;
+        for i in 0..1000 {
+            let expected = if i == 7 { 700 } else { i };
+            assert_eq!(
+                hash_table.get(format!("key_{}", i).as_str()),
+                Some(&expected)
+            );
+        }
+
+        assert!(hash_table.contains_key("key_999"));
+        assert_eq!(hash_table.delete("key_999"), Some(999));
+        assert!(!hash_table.contains_key("key_999"));
+        assert_eq!(hash_table.len(), 999);
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut hash_table = CuckooTable::new(100);
+
+        for i in 0..100 {
+            hash_table.insert(i, i);
+        }
+
+        for i in 0..50 {
+            assert_eq!(hash_table.delete(&i), Some(i));
+        }
+
+        assert_eq!(hash_table.delete(&0), None);
+        assert_eq!(hash_table.size(), 50);
+
+        for i in 0..100 {
+            let expected = if i < 50 { None } else { Some(&i) };
+            assert_eq!(hash_table.get(&i), expected);
+        }
+    }
+
+    #[test]
+    fn test_rehash_on_cycle() {
+        let mut hash_table = CuckooTable::new(64);
+
+        for i in 0..32 {
+            hash_table.insert(i, i);
+        }
+
+        // Force a rebuild at the same size, as a stuck eviction path would
+        hash_table.rehash(hash_table.tables[0].len(), Vec::new());
+
+        assert_eq!(hash_table.size(), 32);
+        for i in 0..32 {
+            assert_eq!(hash_table.get(&i), Some(&i));
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+
+use crate::{HashCache, HashTable, KeyOrder};
+
+/// A view into a single key of a `HashTable`, found with a single hash and
+/// bucket walk and then read or written in place.
+pub enum Entry<'a, K, V, S, H = (), O = ()> {
+    Occupied(OccupiedEntry<'a, K, V, S, H, O>),
+    Vacant(VacantEntry<'a, K, V, S, H, O>),
+}
+
+pub struct OccupiedEntry<'a, K, V, S, H = (), O = ()> {
+    table: &'a mut HashTable<K, V, S, H, O>,
+    // Bucket and chain position of the entry in the (new) bucket array
+    index: usize,
+    position: usize,
+}
+
+pub struct VacantEntry<'a, K, V, S, H = (), O = ()> {
+    table: &'a mut HashTable<K, V, S, H, O>,
+    key: K,
+    hash: u64,
+}
+
+impl<'a, K, V, S, H, O> Entry<'a, K, V, S, H, O>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+    H: HashCache,
+    O: KeyOrder<K>,
+{
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = default(entry.key());
+                entry.insert(value)
+            }
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+
+    // Sets the value whether or not the key was there
+    pub fn insert_entry(self, value: V) -> OccupiedEntry<'a, K, V, S, H, O> {
+        match self {
+            Entry::Occupied(mut entry) => {
+                entry.insert(value);
+                entry
+            }
+            Entry::Vacant(entry) => entry.insert_entry(value),
+        }
+    }
+}
+
+impl<'a, K, V, S, H, O> OccupiedEntry<'a, K, V, S, H, O>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+    H: HashCache,
+    O: KeyOrder<K>,
+{
+    pub(crate) fn new(
+        table: &'a mut HashTable<K, V, S, H, O>,
+        index: usize,
+        position: usize,
+    ) -> Self {
+        OccupiedEntry {
+            table,
+            index,
+            position,
+        }
+    }
+
+    pub fn key(&self) -> &K {
+        &self.table.raw.buckets[self.index][self.position].key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.table.raw.buckets[self.index][self.position].value
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.table.raw.buckets[self.index][self.position].value
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.table.raw.buckets[self.index][self.position].value
+    }
+
+    // Replaces the value, returning the old one
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        let kv = self.table.remove_at(self.index, self.position);
+        (kv.key, kv.value)
+    }
+}
+
+impl<'a, K, V, S, H, O> VacantEntry<'a, K, V, S, H, O>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+    H: HashCache,
+    O: KeyOrder<K>,
+{
+    pub(crate) fn new(table: &'a mut HashTable<K, V, S, H, O>, key: K, hash: u64) -> Self {
+        VacantEntry { table, key, hash }
+    }
+
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.insert_entry(value).into_mut()
+    }
+
+    pub fn insert_entry(self, value: V) -> OccupiedEntry<'a, K, V, S, H, O> {
+        let (index, position) = self.table.insert_unique(self.hash, self.key, value);
+        OccupiedEntry::new(self.table, index, position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counting() {
+        let mut hash_table: HashTable<&str, u64> = HashTable::new(10);
+
+        for word in "a b a c b a".split(' ') {
+            *hash_table.entry(word).or_insert(0) += 1;
+        }
+
+        assert_eq!(hash_table.get("a"), Some(&3));
+        assert_eq!(hash_table.get("b"), Some(&2));
+        assert_eq!(hash_table.get("c"), Some(&1));
+        assert_eq!(hash_table.size(), 3);
+    }
+
+    #[test]
+    fn test_grouping() {
+        let mut hash_table: HashTable<u64, Vec<u64>> = HashTable::new(4);
+
+        for i in 0..100 {
+            hash_table.entry(i % 7).or_default().push(i);
+        }
+
+        assert_eq!(hash_table.size(), 7);
+        assert_eq!(hash_table.get(&3).map(Vec::len), Some(14));
+        assert_eq!(hash_table.get(&6).and_then(|v| v.last()), Some(&97));
+    }
+
+    #[test]
+    fn test_and_modify() {
+        let mut hash_table: HashTable<String, u64> = HashTable::new(10);
+
+        hash_table
+            .entry("key1".to_string())
+            .and_modify(|v| *v += 10)
+            .or_insert_with(|| 1);
+        hash_table
+            .entry("key1".to_string())
+            .and_modify(|v| *v += 10)
+            .or_insert_with(|| 1);
+
+        assert_eq!(hash_table.get("key1"), Some(&11));
+
+        let length = hash_table
+            .entry("key22".to_string())
+            .or_insert_with_key(|key| key.len() as u64);
+        assert_eq!(*length, 5);
+    }
+
+    #[test]
+    fn test_occupied_and_vacant() {
+        let mut hash_table: HashTable<String, u64> = HashTable::new(10);
+
+        let entry = hash_table.entry("key1".to_string()).insert_entry(1);
+        assert_eq!(entry.key(), "key1");
+        assert_eq!(entry.get(), &1);
+
+        match hash_table.entry("key1".to_string()) {
+            Entry::Occupied(mut entry) => assert_eq!(entry.insert(2), 1),
+            Entry::Vacant(_) => panic!("key1 is present"),
+        }
+
+        match hash_table.entry("key2".to_string()) {
+            Entry::Occupied(_) => panic!("key2 is absent"),
+            Entry::Vacant(entry) => assert_eq!(entry.into_key(), "key2"),
+        }
+
+        match hash_table.entry("key1".to_string()) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove_entry(), ("key1".to_string(), 2)),
+            Entry::Vacant(_) => panic!("key1 is present"),
+        }
+
+        assert_eq!(hash_table.get("key1"), None);
+        assert_eq!(hash_table.size(), 0);
+    }
+}
@@ -0,0 +1,39 @@
+use std::alloc::Layout;
+use std::error::Error;
+use std::fmt;
+
+/// Why `HashTable::try_reserve` or `HashTable::try_insert` couldn't make room.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TryReserveError {
+    // The requested bucket count doesn't fit in `usize` or `isize::MAX` bytes
+    CapacityOverflow,
+    // The allocator refused a bucket array of this layout
+    AllocError { layout: Layout },
+}
+
+impl TryReserveError {
+    // What the infallible API does instead of returning the error
+    pub(crate) fn handle(self) -> ! {
+        match self {
+            TryReserveError::CapacityOverflow => panic!("capacity overflow"),
+            TryReserveError::AllocError { layout } => std::alloc::handle_alloc_error(layout),
+        }
+    }
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryReserveError::CapacityOverflow => {
+                f.write_str("capacity overflow while reserving hash table buckets")
+            }
+            TryReserveError::AllocError { layout } => write!(
+                f,
+                "memory allocation of {} bytes for hash table buckets failed",
+                layout.size()
+            ),
+        }
+    }
+}
+
+impl Error for TryReserveError {}
@@ -0,0 +1,73 @@
+// Hash functions to build a `HashTable` with, from fastest and unkeyed to keyed:
+//
+//     HashTable::with_hasher(FxBuildHasher::default())
+//
+// FxHash suits integer keys nobody picks adversarially. wyhash and XXH3 take a
+// random seed. In the lookup bench's `hash_one` rows wyhash takes about half of
+// SipHash's time on a 20 byte `String` and a third on a `u64`; XXH3 is barely
+// faster than SipHash on the string and takes about 60% of its time on the `u64`.
+// SipHash-1-3 is what `RandomState` runs, for keys that come from untrusted input.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+mod fx;
+mod sip;
+mod wy;
+mod xxh3;
+
+pub use fx::{FxBuildHasher, FxHasher};
+pub use sip::{SipBuildHasher, SipHasher, SipHasher13, SipHasher24};
+pub use wy::{wyhash, WyBuildHasher, WyHasher};
+pub use xxh3::{xxh3_64, Xxh3BuildHasher, Xxh3Hasher};
+
+// Seeds and keys come from std's per-process randomness
+fn random_seed() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+// Bytes 0, 1, 2, ..., the input the reference test vectors are computed over
+#[cfg(test)]
+fn test_input(len: usize) -> Vec<u8> {
+    (0..len).map(|i| i as u8).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HashTable;
+
+    fn round_trip<S: BuildHasher>(hash_builder: S) {
+        let mut hash_table: HashTable<String, usize, S> = HashTable::with_hasher(hash_builder);
+
+        for i in 0..1000 {
+            hash_table.insert(format!("key_{}", i), i);
+        }
+
+        for i in 0..1000 {
+            assert_eq!(hash_table.get(&format!("key_{}", i)), Some(&i));
+        }
+        assert_eq!(hash_table.get("key_1000"), None);
+    }
+
+    #[test]
+    fn test_hash_tables() {
+        round_trip(FxBuildHasher::default());
+        round_trip(SipBuildHasher::new());
+        round_trip(WyBuildHasher::new());
+        round_trip(Xxh3BuildHasher::new());
+    }
+
+    #[test]
+    fn test_random_seeds() {
+        // Two builders hash the same key differently
+        assert_ne!(
+            WyBuildHasher::new().hash_one(1),
+            WyBuildHasher::new().hash_one(1)
+        );
+        assert_ne!(
+            SipBuildHasher::new().hash_one(1),
+            SipBuildHasher::new().hash_one(1)
+        );
+    }
+}
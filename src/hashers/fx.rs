@@ -0,0 +1,99 @@
+use std::hash::{BuildHasherDefault, Hasher};
+
+// rustc's multiplier, 2^64 / pi rounded to odd
+const SEED: u64 = 0x517c_c1b7_2722_0a95;
+
+/// The 64-bit FxHash from rustc: one rotate, xor and multiply per word.
+///
+/// By far the fastest here on integer keys, but unkeyed and easily flooded, and its
+/// low bits mix poorly: a lone integer key `k` hashes to `k * SEED`, so keys whose
+/// low 32 bits are zero get hashes whose low 32 bits are zero too. The tests lean on
+/// that to pile keys into one slot. Pair it with `IndexMode::MultiplyShift` rather
+/// than a power of two modulo.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FxHasher {
+    hash: u64,
+}
+
+pub type FxBuildHasher = BuildHasherDefault<FxHasher>;
+
+impl FxHasher {
+    fn add_to_hash(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for FxHasher {
+    // Native endian words, then a 4, 2 and 1 byte tail, like rustc-hash
+    fn write(&mut self, mut bytes: &[u8]) {
+        while let Some((word, rest)) = bytes.split_first_chunk::<8>() {
+            self.add_to_hash(u64::from_ne_bytes(*word));
+            bytes = rest;
+        }
+        if let Some((word, rest)) = bytes.split_first_chunk::<4>() {
+            self.add_to_hash(u32::from_ne_bytes(*word) as u64);
+            bytes = rest;
+        }
+        if let Some((word, rest)) = bytes.split_first_chunk::<2>() {
+            self.add_to_hash(u16::from_ne_bytes(*word) as u64);
+            bytes = rest;
+        }
+        if let Some(&byte) = bytes.first() {
+            self.add_to_hash(byte as u64);
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.add_to_hash(i as u64);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.add_to_hash(i as u64);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.add_to_hash(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.add_to_hash(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashers::test_input;
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn test_vectors() {
+        // rustc-hash 1.1 on a 64-bit target, input 0, 1, 2, ...
+        let vectors = [
+            (3, 0x892e_9d0d_7c11_a991),
+            (4, 0x25a5_6b8a_f634_9500),
+            (9, 0xb334_3772_4ab6_680b),
+            (17, 0x61ae_9e5f_b464_c002),
+            (100, 0x10b6_5e85_fd7b_98ad),
+        ];
+
+        for (len, expected) in vectors {
+            let mut hasher = FxHasher::default();
+            hasher.write(&test_input(len));
+            assert_eq!(hasher.finish(), expected, "{} bytes", len);
+        }
+
+        let mut hasher = FxHasher::default();
+        hasher.write_u64(42);
+        hasher.write_u32(7);
+        assert_eq!(hasher.finish(), 0x4e8e_a531_6a7c_ca3c);
+    }
+}
@@ -0,0 +1,202 @@
+use std::hash::{BuildHasher, Hasher};
+
+const P0: u64 = 0xa076_1d64_78bd_642f;
+const P1: u64 = 0xe703_7ed1_a0b4_28db;
+const P2: u64 = 0x8ebc_6af0_9c88_c6e3;
+const P3: u64 = 0x5899_65cc_7537_4cc3;
+const P4: u64 = 0x1d8e_4e27_c47d_124f;
+const P5: u64 = 0xeb44_acca_b455_d165;
+
+/// wyhash of `bytes` under `seed`, ported from version 1 of Wang Yi's reference.
+pub fn wyhash(bytes: &[u8], seed: u64) -> u64 {
+    finish(absorb(bytes, seed), bytes.len() as u64)
+}
+
+// Folds the 128-bit product back to 64 bits
+fn mum(a: u64, b: u64) -> u64 {
+    let product = a as u128 * b as u128;
+    (product ^ (product >> 64)) as u64
+}
+
+fn read64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().unwrap())
+}
+
+fn read32(bytes: &[u8]) -> u64 {
+    u32::from_le_bytes(bytes[..4].try_into().unwrap()) as u64
+}
+
+// The reference reads tail words as two swapped 32-bit halves
+fn read64_swapped(bytes: &[u8]) -> u64 {
+    (read32(bytes) << 32) | read32(&bytes[4..])
+}
+
+// 1 to 8 trailing bytes, in the reference's byte order
+fn read_rest(bytes: &[u8]) -> u64 {
+    let byte = |i: usize| bytes[i] as u64;
+
+    match bytes.len() {
+        1 => byte(0),
+        2 => (byte(1) << 8) | byte(0),
+        3 => (byte(1) << 16) | (byte(0) << 8) | byte(2),
+        4 => read32(bytes),
+        5 => (read32(bytes) << 8) | byte(4),
+        6 => (read32(bytes) << 16) | (byte(5) << 8) | byte(4),
+        7 => (read32(bytes) << 24) | (byte(5) << 16) | (byte(4) << 8) | byte(6),
+        8 => read64_swapped(bytes),
+        _ => unreachable!("tails are 1 to 8 bytes"),
+    }
+}
+
+// Everything but the length, so consecutive writes can chain through the seed
+fn absorb(bytes: &[u8], mut seed: u64) -> u64 {
+    let mut chunks = bytes.chunks_exact(32);
+    for chunk in &mut chunks {
+        seed = mum(
+            seed ^ P0,
+            mum(read64(chunk) ^ P1, read64(&chunk[8..]) ^ P2)
+                ^ mum(read64(&chunk[16..]) ^ P3, read64(&chunk[24..]) ^ P4),
+        );
+    }
+    seed ^= P0;
+
+    let rest = chunks.remainder();
+    match rest.len() {
+        0 => seed,
+        1..=8 => mum(seed, read_rest(rest) ^ P1),
+        9..=16 => mum(read64_swapped(rest) ^ seed, read_rest(&rest[8..]) ^ P2),
+        17..=24 => {
+            mum(read64_swapped(rest) ^ seed, read64_swapped(&rest[8..]) ^ P2)
+                ^ mum(seed, read_rest(&rest[16..]) ^ P3)
+        }
+        _ => {
+            mum(read64_swapped(rest) ^ seed, read64_swapped(&rest[8..]) ^ P2)
+                ^ mum(
+                    read64_swapped(&rest[16..]) ^ seed,
+                    read_rest(&rest[24..]) ^ P4,
+                )
+        }
+    }
+}
+
+fn finish(seed: u64, len: u64) -> u64 {
+    mum(seed, len ^ P5)
+}
+
+/// Streams writes through `wyhash`: a single write hashes like `wyhash(bytes, seed)`.
+///
+/// A handful of 128-bit multiplies per 32 bytes, where SipHash runs a round per 8.
+/// Seeded, but not a PRF: treat it as flood resistant only while the seed stays
+/// secret and inputs aren't crafted against it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WyHasher {
+    seed: u64,
+    len: u64,
+}
+
+impl WyHasher {
+    pub fn with_seed(seed: u64) -> Self {
+        WyHasher { seed, len: 0 }
+    }
+}
+
+impl Hasher for WyHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.seed = absorb(bytes, self.seed);
+        self.len += bytes.len() as u64;
+    }
+
+    // What `absorb` does with a lone 1 or 8 byte tail, without the length dispatch,
+    // so a `str` terminator or an integer key costs one multiply
+    fn write_u8(&mut self, i: u8) {
+        self.seed = mum(self.seed ^ P0, i as u64 ^ P1);
+        self.len += 1;
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.seed = mum(self.seed ^ P0, read64_swapped(&i.to_ne_bytes()) ^ P1);
+        self.len += 8;
+    }
+
+    fn finish(&self) -> u64 {
+        finish(self.seed, self.len)
+    }
+}
+
+/// Builds `WyHasher`s sharing one seed, random unless given.
+#[derive(Clone, Copy, Debug)]
+pub struct WyBuildHasher {
+    seed: u64,
+}
+
+impl WyBuildHasher {
+    pub fn new() -> Self {
+        Self::with_seed(super::random_seed())
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        WyBuildHasher { seed }
+    }
+}
+
+impl Default for WyBuildHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BuildHasher for WyBuildHasher {
+    type Hasher = WyHasher;
+
+    fn build_hasher(&self) -> WyHasher {
+        WyHasher::with_seed(self.seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashers::test_input;
+
+    #[test]
+    fn test_vectors() {
+        // `wyhash(0, 1, .., len - 1, seed = len)`, one length per code path. These pin
+        // this port's own output against regressions; they aren't published vectors.
+        let vectors = [
+            (0, 0xf961_f936_e29c_9345),
+            (1, 0x83fc_be65_1268_30a3),
+            (3, 0xb0f9_4152_0b1a_d95d),
+            (8, 0x276e_8d03_15af_0b78),
+            (9, 0x8712_dbf1_d543_727b),
+            (16, 0x9510_7695_67e2_b9f5),
+            (17, 0xe4c8_addf_5e52_d332),
+            (100, 0x20b2_de37_02e3_9858),
+            (241, 0x4aaf_b956_36c6_ff3b),
+        ];
+
+        for (len, expected) in vectors {
+            let input = test_input(len);
+            assert_eq!(wyhash(&input, len as u64), expected, "{} bytes", len);
+
+            let mut hasher = WyHasher::with_seed(len as u64);
+            hasher.write(&input);
+            assert_eq!(hasher.finish(), expected, "{} bytes", len);
+        }
+    }
+
+    #[test]
+    fn test_integer_writes() {
+        // Analysis replays integer writes as byte writes, so they must agree
+        let mut integers = WyHasher::with_seed(3);
+        integers.write(b"key");
+        integers.write_u8(0xff);
+        integers.write_u64(0x0123_4567_89ab_cdef);
+
+        let mut bytes = WyHasher::with_seed(3);
+        bytes.write(b"key");
+        bytes.write(&[0xff]);
+        bytes.write(&0x0123_4567_89ab_cdef_u64.to_ne_bytes());
+
+        assert_eq!(integers.finish(), bytes.finish());
+    }
+}
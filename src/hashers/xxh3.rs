@@ -0,0 +1,370 @@
+use std::hash::{BuildHasher, Hasher};
+
+const PRIME32_1: u64 = 0x9e37_79b1;
+const PRIME32_2: u64 = 0x85eb_ca77;
+const PRIME32_3: u64 = 0xc2b2_ae3d;
+const PRIME64_1: u64 = 0x9e37_79b1_85eb_ca87;
+const PRIME64_2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+const PRIME64_3: u64 = 0x1656_67b1_9e37_79f9;
+const PRIME64_4: u64 = 0x85eb_ca77_c2b2_ae63;
+const PRIME64_5: u64 = 0x27d4_eb2f_1656_67c5;
+
+// Inputs over 240 bytes are cut into 64-byte stripes, each feeding 8 accumulators
+const STRIPE_LEN: usize = 64;
+const SECRET_CONSUME_RATE: usize = 8;
+const MID_SIZE_MAX: usize = 240;
+const SECRET_SIZE_MIN: usize = 136;
+const SECRET_MERGEACCS_START: usize = 11;
+const SECRET_LASTACC_START: usize = 7;
+
+const DEFAULT_SECRET: [u8; 192] = [
+    0xb8, 0xfe, 0x6c, 0x39, 0x23, 0xa4, 0x4b, 0xbe, 0x7c, 0x01, 0x81, 0x2c, 0xf7, 0x21, 0xad, 0x1c,
+    0xde, 0xd4, 0x6d, 0xe9, 0x83, 0x90, 0x97, 0xdb, 0x72, 0x40, 0xa4, 0xa4, 0xb7, 0xb3, 0x67, 0x1f,
+    0xcb, 0x79, 0xe6, 0x4e, 0xcc, 0xc0, 0xe5, 0x78, 0x82, 0x5a, 0xd0, 0x7d, 0xcc, 0xff, 0x72, 0x21,
+    0xb8, 0x08, 0x46, 0x74, 0xf7, 0x43, 0x24, 0x8e, 0xe0, 0x35, 0x90, 0xe6, 0x81, 0x3a, 0x26, 0x4c,
+    0x3c, 0x28, 0x52, 0xbb, 0x91, 0xc3, 0x00, 0xcb, 0x88, 0xd0, 0x65, 0x8b, 0x1b, 0x53, 0x2e, 0xa3,
+    0x71, 0x64, 0x48, 0x97, 0xa2, 0x0d, 0xf9, 0x4e, 0x38, 0x19, 0xef, 0x46, 0xa9, 0xde, 0xac, 0xd8,
+    0xa8, 0xfa, 0x76, 0x3f, 0xe3, 0x9c, 0x34, 0x3f, 0xf9, 0xdc, 0xbb, 0xc7, 0xc7, 0x0b, 0x4f, 0x1d,
+    0x8a, 0x51, 0xe0, 0x4b, 0xcd, 0xb4, 0x59, 0x31, 0xc8, 0x9f, 0x7e, 0xc9, 0xd9, 0x78, 0x73, 0x64,
+    0xea, 0xc5, 0xac, 0x83, 0x34, 0xd3, 0xeb, 0xc3, 0xc5, 0x81, 0xa0, 0xff, 0xfa, 0x13, 0x63, 0xeb,
+    0x17, 0x0d, 0xdd, 0x51, 0xb7, 0xf0, 0xda, 0x49, 0xd3, 0x16, 0x55, 0x26, 0x29, 0xd4, 0x68, 0x9e,
+    0x2b, 0x16, 0xbe, 0x58, 0x7d, 0x47, 0xa1, 0xfc, 0x8f, 0xf8, 0xb8, 0xd1, 0x7a, 0xd0, 0x31, 0xce,
+    0x45, 0xcb, 0x3a, 0x8f, 0x95, 0x16, 0x04, 0x28, 0xaf, 0xd7, 0xfb, 0xca, 0xbb, 0x4b, 0x40, 0x7e,
+];
+
+/// XXH3-64 of `bytes` under `seed`, as in xxHash 0.8. Seed 0 is the unseeded hash.
+pub fn xxh3_64(bytes: &[u8], seed: u64) -> u64 {
+    let secret = &DEFAULT_SECRET;
+
+    match bytes.len() {
+        0 => xxh64_avalanche(seed ^ read64(secret, 56) ^ read64(secret, 64)),
+        1..=3 => hash_1to3(bytes, seed, secret),
+        4..=8 => hash_4to8(bytes, seed, secret),
+        9..=16 => hash_9to16(bytes, seed, secret),
+        17..=128 => hash_17to128(bytes, seed, secret),
+        129..=MID_SIZE_MAX => hash_129to240(bytes, seed, secret),
+        // The long loop takes the seed through a secret derived from it
+        _ if seed == 0 => hash_long(bytes, secret),
+        _ => hash_long(bytes, &custom_secret(seed)),
+    }
+}
+
+fn read32(bytes: &[u8], offset: usize) -> u64 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as u64
+}
+
+fn read64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+fn mul128_fold64(a: u64, b: u64) -> u64 {
+    let product = a as u128 * b as u128;
+    (product as u64) ^ ((product >> 64) as u64)
+}
+
+fn xxh64_avalanche(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(PRIME64_2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(PRIME64_3);
+    hash ^ (hash >> 32)
+}
+
+fn avalanche(mut hash: u64) -> u64 {
+    hash ^= hash >> 37;
+    hash = hash.wrapping_mul(0x1656_6791_9e37_79f9);
+    hash ^ (hash >> 32)
+}
+
+fn strong_avalanche(mut hash: u64, len: u64) -> u64 {
+    hash ^= hash.rotate_left(49) ^ hash.rotate_left(24);
+    hash = hash.wrapping_mul(0x9fb2_1c65_1e98_df25);
+    hash ^= (hash >> 35).wrapping_add(len);
+    hash = hash.wrapping_mul(0x9fb2_1c65_1e98_df25);
+    hash ^ (hash >> 28)
+}
+
+fn hash_1to3(bytes: &[u8], seed: u64, secret: &[u8]) -> u64 {
+    let len = bytes.len();
+    let combined = ((bytes[0] as u64) << 16)
+        | ((bytes[len >> 1] as u64) << 24)
+        | bytes[len - 1] as u64
+        | ((len as u64) << 8);
+    let flip = (read32(secret, 0) ^ read32(secret, 4)).wrapping_add(seed);
+
+    xxh64_avalanche(combined ^ flip)
+}
+
+fn hash_4to8(bytes: &[u8], mut seed: u64, secret: &[u8]) -> u64 {
+    let len = bytes.len();
+    seed ^= ((seed as u32).swap_bytes() as u64) << 32;
+
+    let flip = (read64(secret, 8) ^ read64(secret, 16)).wrapping_sub(seed);
+    let input = read32(bytes, len - 4).wrapping_add(read32(bytes, 0) << 32);
+
+    strong_avalanche(input ^ flip, len as u64)
+}
+
+fn hash_9to16(bytes: &[u8], seed: u64, secret: &[u8]) -> u64 {
+    let len = bytes.len();
+    let flip_lo = (read64(secret, 24) ^ read64(secret, 32)).wrapping_add(seed);
+    let flip_hi = (read64(secret, 40) ^ read64(secret, 48)).wrapping_sub(seed);
+    let lo = read64(bytes, 0) ^ flip_lo;
+    let hi = read64(bytes, len - 8) ^ flip_hi;
+
+    avalanche(
+        (len as u64)
+            .wrapping_add(lo.swap_bytes())
+            .wrapping_add(hi)
+            .wrapping_add(mul128_fold64(lo, hi)),
+    )
+}
+
+// 16 input bytes against 16 secret bytes
+fn mix16(bytes: &[u8], offset: usize, secret: &[u8], secret_offset: usize, seed: u64) -> u64 {
+    let lo = read64(bytes, offset) ^ read64(secret, secret_offset).wrapping_add(seed);
+    let hi = read64(bytes, offset + 8) ^ read64(secret, secret_offset + 8).wrapping_sub(seed);
+
+    mul128_fold64(lo, hi)
+}
+
+fn hash_17to128(bytes: &[u8], seed: u64, secret: &[u8]) -> u64 {
+    let len = bytes.len();
+    let mut acc = (len as u64).wrapping_mul(PRIME64_1);
+
+    // Pairs of 16-byte blocks from both ends, meeting in the middle
+    for i in 0..=(len - 1) / 32 {
+        acc = acc
+            .wrapping_add(mix16(bytes, 16 * i, secret, 32 * i, seed))
+            .wrapping_add(mix16(bytes, len - 16 * (i + 1), secret, 32 * i + 16, seed));
+    }
+
+    avalanche(acc)
+}
+
+fn hash_129to240(bytes: &[u8], seed: u64, secret: &[u8]) -> u64 {
+    let len = bytes.len();
+    let mut acc = (len as u64).wrapping_mul(PRIME64_1);
+
+    for i in 0..8 {
+        acc = acc.wrapping_add(mix16(bytes, 16 * i, secret, 16 * i, seed));
+    }
+    acc = avalanche(acc);
+
+    for i in 8..len / 16 {
+        acc = acc.wrapping_add(mix16(bytes, 16 * i, secret, 16 * (i - 8) + 3, seed));
+    }
+    acc = acc.wrapping_add(mix16(bytes, len - 16, secret, SECRET_SIZE_MIN - 17, seed));
+
+    avalanche(acc)
+}
+
+// The default secret with the seed added to one half of every 16 bytes and
+// subtracted from the other
+fn custom_secret(seed: u64) -> [u8; 192] {
+    let mut secret = [0; 192];
+
+    for i in (0..secret.len()).step_by(16) {
+        let lo = read64(&DEFAULT_SECRET, i).wrapping_add(seed);
+        let hi = read64(&DEFAULT_SECRET, i + 8).wrapping_sub(seed);
+        secret[i..i + 8].copy_from_slice(&lo.to_le_bytes());
+        secret[i + 8..i + 16].copy_from_slice(&hi.to_le_bytes());
+    }
+
+    secret
+}
+
+fn accumulate_512(acc: &mut [u64; 8], stripe: &[u8], secret: &[u8]) {
+    for i in 0..8 {
+        let value = read64(stripe, 8 * i);
+        let keyed = value ^ read64(secret, 8 * i);
+
+        acc[i ^ 1] = acc[i ^ 1].wrapping_add(value);
+        acc[i] = acc[i].wrapping_add((keyed & 0xffff_ffff).wrapping_mul(keyed >> 32));
+    }
+}
+
+fn scramble(acc: &mut [u64; 8], secret: &[u8]) {
+    for (i, lane) in acc.iter_mut().enumerate() {
+        *lane = (*lane ^ (*lane >> 47) ^ read64(secret, 8 * i)).wrapping_mul(PRIME32_1);
+    }
+}
+
+fn accumulate(acc: &mut [u64; 8], bytes: &[u8], secret: &[u8], stripes: usize) {
+    for i in 0..stripes {
+        accumulate_512(
+            acc,
+            &bytes[i * STRIPE_LEN..],
+            &secret[i * SECRET_CONSUME_RATE..],
+        );
+    }
+}
+
+fn hash_long(bytes: &[u8], secret: &[u8]) -> u64 {
+    let len = bytes.len();
+    let mut acc = [
+        PRIME32_3, PRIME64_1, PRIME64_2, PRIME64_3, PRIME64_4, PRIME32_2, PRIME64_5, PRIME32_1,
+    ];
+
+    // Every block runs the whole secret once, then scrambles the accumulators
+    let stripes_per_block = (secret.len() - STRIPE_LEN) / SECRET_CONSUME_RATE;
+    let block_len = STRIPE_LEN * stripes_per_block;
+    let blocks = (len - 1) / block_len;
+
+    for block in 0..blocks {
+        accumulate(
+            &mut acc,
+            &bytes[block * block_len..],
+            secret,
+            stripes_per_block,
+        );
+        scramble(&mut acc, &secret[secret.len() - STRIPE_LEN..]);
+    }
+
+    let stripes = (len - 1 - block_len * blocks) / STRIPE_LEN;
+    accumulate(&mut acc, &bytes[blocks * block_len..], secret, stripes);
+    accumulate_512(
+        &mut acc,
+        &bytes[len - STRIPE_LEN..],
+        &secret[secret.len() - STRIPE_LEN - SECRET_LASTACC_START..],
+    );
+
+    let mut result = (len as u64).wrapping_mul(PRIME64_1);
+    for i in 0..4 {
+        let offset = SECRET_MERGEACCS_START + 16 * i;
+        result = result.wrapping_add(mul128_fold64(
+            acc[2 * i] ^ read64(secret, offset),
+            acc[2 * i + 1] ^ read64(secret, offset + 8),
+        ));
+    }
+
+    avalanche(result)
+}
+
+/// Streams writes through `xxh3_64`: a single write hashes like `xxh3_64(bytes, seed)`,
+/// and every further write is hashed under the hash so far as its seed. Nothing is
+/// buffered, so a key's one `write` plus a `str` terminator costs two short hashes.
+///
+/// Seeded, but like wyhash not built to resist inputs crafted against a known seed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Xxh3Hasher {
+    seed: u64,
+    // Hash of the writes so far, none before the first
+    hash: Option<u64>,
+}
+
+impl Xxh3Hasher {
+    pub fn with_seed(seed: u64) -> Self {
+        Xxh3Hasher { seed, hash: None }
+    }
+
+    fn chain_seed(&self) -> u64 {
+        self.hash.unwrap_or(self.seed)
+    }
+}
+
+impl Hasher for Xxh3Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.hash = Some(xxh3_64(bytes, self.chain_seed()));
+    }
+
+    // Integer writes skip the length dispatch, hashing like `write` on their bytes
+    fn write_u8(&mut self, i: u8) {
+        self.hash = Some(hash_1to3(&[i], self.chain_seed(), &DEFAULT_SECRET));
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.hash = Some(hash_4to8(
+            &i.to_ne_bytes(),
+            self.chain_seed(),
+            &DEFAULT_SECRET,
+        ));
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash.unwrap_or_else(|| xxh3_64(&[], self.seed))
+    }
+}
+
+/// Builds `Xxh3Hasher`s sharing one seed, random unless given.
+#[derive(Clone, Copy, Debug)]
+pub struct Xxh3BuildHasher {
+    seed: u64,
+}
+
+impl Xxh3BuildHasher {
+    pub fn new() -> Self {
+        Self::with_seed(super::random_seed())
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Xxh3BuildHasher { seed }
+    }
+}
+
+impl Default for Xxh3BuildHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BuildHasher for Xxh3BuildHasher {
+    type Hasher = Xxh3Hasher;
+
+    fn build_hasher(&self) -> Xxh3Hasher {
+        Xxh3Hasher::with_seed(self.seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashers::test_input;
+
+    #[test]
+    fn test_vectors() {
+        // xxHash 0.8 on input 0, 1, 2, ..., one length per code path: unseeded, and
+        // seeded with 0x9e3779b97f4a7c15
+        let vectors = [
+            (0, 0x2d06_8005_38d3_94c2, 0x602b_0e2c_d666_2c8b),
+            (3, 0x5f42_99fc_161c_9cbb, 0xbe1f_d1f5_03b5_d59e),
+            (8, 0x3a1c_2d7c_85af_88f8, 0xb82d_9ef5_fd6b_3172),
+            (16, 0x8355_e3a6_f617_70db, 0x3d39_2960_bfd9_df8a),
+            (100, 0x004e_4f92_1a64_bd1c, 0x19cf_7629_02c5_f037),
+            (129, 0xec76_42b4_31ba_3e5a, 0x747f_159f_dd2d_2177),
+            (240, 0x375a_384d_957f_e865, 0xe6e7_66db_0868_c372),
+            (241, 0x02e8_cd95_421c_6d02, 0x1721_14de_208c_5a80),
+            (1000, 0xd33d_d80b_46f6_0e50, 0xc51f_31df_d07c_0b06),
+        ];
+
+        for (len, unseeded, seeded) in vectors {
+            let input = test_input(len);
+            assert_eq!(xxh3_64(&input, 0), unseeded, "{} bytes", len);
+            assert_eq!(
+                xxh3_64(&input, 0x9e37_79b9_7f4a_7c15),
+                seeded,
+                "{} bytes",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn test_chained_writes() {
+        let input = test_input(100);
+
+        let mut hasher = Xxh3Hasher::with_seed(5);
+        assert_eq!(hasher.finish(), xxh3_64(&[], 5));
+        hasher.write(&input);
+        assert_eq!(hasher.finish(), xxh3_64(&input, 5));
+
+        // Every later write is seeded with the hash so far
+        hasher.write(&input[..20]);
+        hasher.write_u8(0xff);
+        hasher.write_u64(42);
+        let chained = [&input[..20], &[0xff], &42u64.to_ne_bytes()]
+            .iter()
+            .fold(xxh3_64(&input, 5), |hash, bytes| xxh3_64(bytes, hash));
+        assert_eq!(hasher.finish(), chained);
+    }
+}
@@ -0,0 +1,353 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+
+use crate::KV;
+
+// Neighborhood size: every entry lives at most H - 1 slots after its home bucket
+const H: usize = 32;
+
+const MAX_LOAD_FACTOR: f64 = 0.9;
+
+/// Hopscotch hashing: every key is kept within the `H` slot neighborhood of its
+/// home bucket, and each home bucket has a bitmap of which neighborhood slots
+/// hold its entries. A lookup only checks the slots flagged in one bitmap, which
+/// sit within a couple of cache lines. When the closest free slot is too far, it's
+/// hopped backwards by moving entries that may legally move into it.
+#[derive(Clone, Debug)]
+pub struct HopscotchTable<K, V, S = RandomState> {
+    slots: Vec<Option<KV<K, V>>>,
+    // Bit `i` of `hop_info[b]` -> slot `b + i` holds an entry whose home is `b`
+    hop_info: Vec<u32>,
+    size: usize,
+    hash_builder: S,
+}
+
+impl<K, V> HopscotchTable<K, V, RandomState>
+where
+    K: Hash + Eq,
+{
+    pub fn new(with_capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(with_capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> HopscotchTable<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn with_capacity_and_hasher(with_capacity: usize, hash_builder: S) -> Self {
+        let len = ((with_capacity as f64 / MAX_LOAD_FACTOR).ceil() as usize)
+            .max(H)
+            .next_power_of_two();
+
+        Self::with_len(len, hash_builder)
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(index) = self.find(&key) {
+            let kv = self.slots[index]
+                .as_mut()
+                .expect("neighborhood slot is occupied");
+            return Some(mem::replace(&mut kv.value, value));
+        }
+
+        if (self.size + 1) as f64 > self.slots.len() as f64 * MAX_LOAD_FACTOR {
+            self.resize();
+        }
+
+        let mut kv = KV::new(key, value);
+        loop {
+            match self.place(kv) {
+                Ok(()) => break,
+                Err(homeless) => {
+                    // No free slot could be hopped into the neighborhood
+                    kv = homeless;
+                    self.resize();
+                }
+            }
+        }
+
+        self.size += 1;
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(key)?;
+        self.slots[index].as_ref().map(|kv| &kv.value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(key)?;
+        self.slots[index].as_mut().map(|kv| &mut kv.value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    pub fn delete<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(key)?;
+        let home = self.create_index(key);
+
+        self.hop_info[home] &= !(1 << self.distance(home, index));
+        self.size -= 1;
+        self.slots[index].take().map(|kv| kv.value)
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let home = self.create_index(key);
+
+        Neighbors(self.hop_info[home])
+            .map(|offset| (home + offset) & self.mask())
+            .find(|&index| {
+                self.slots[index]
+                    .as_ref()
+                    .is_some_and(|kv| kv.key.borrow() == key)
+            })
+    }
+
+    fn place(&mut self, kv: KV<K, V>) -> Result<(), KV<K, V>> {
+        let home = self.create_index(&kv.key);
+
+        let Some(mut free) = (0..self.slots.len())
+            .map(|offset| (home + offset) & self.mask())
+            .find(|&index| self.slots[index].is_none())
+        else {
+            return Err(kv);
+        };
+
+        while self.distance(home, free) >= H {
+            match self.hop_closer(free) {
+                Some(index) => free = index,
+                None => return Err(kv),
+            }
+        }
+
+        self.hop_info[home] |= 1 << self.distance(home, free);
+        self.slots[free] = Some(kv);
+
+        Ok(())
+    }
+
+    // Moves the furthest-back entry that may legally live in `free` into it, and
+    // returns the slot it vacated, which is closer to the insert's home bucket
+    fn hop_closer(&mut self, free: usize) -> Option<usize> {
+        for distance in (1..H).rev() {
+            let bucket = (free + self.slots.len() - distance) & self.mask();
+
+            // Only entries sitting before `free` can move forward into it
+            let offset = Neighbors(self.hop_info[bucket])
+                .next()
+                .filter(|&offset| offset < distance);
+
+            if let Some(offset) = offset {
+                let from = (bucket + offset) & self.mask();
+
+                self.slots[free] = self.slots[from].take();
+                self.hop_info[bucket] &= !(1 << offset);
+                self.hop_info[bucket] |= 1 << distance;
+
+                return Some(from);
+            }
+        }
+
+        None
+    }
+
+    fn create_index<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        (self.hash_builder.hash_one(key) as usize) & self.mask()
+    }
+
+    fn distance(&self, home: usize, index: usize) -> usize {
+        (index + self.slots.len() - home) & self.mask()
+    }
+
+    fn mask(&self) -> usize {
+        self.slots.len() - 1
+    }
+
+    fn resize(&mut self) {
+        let new_len = self.slots.len() * 2;
+        let old_slots = mem::replace(&mut self.slots, (0..new_len).map(|_| None).collect());
+        self.hop_info = vec![0; new_len];
+        self.size = 0;
+
+        for kv in old_slots.into_iter().flatten() {
+            self.insert(kv.key, kv.value);
+        }
+    }
+
+    fn with_len(len: usize, hash_builder: S) -> Self {
+        HopscotchTable {
+            slots: (0..len).map(|_| None).collect(),
+            hop_info: vec![0; len],
+            size: 0,
+            hash_builder,
+        }
+    }
+}
+
+// Offsets of the set bits in a neighborhood bitmap, lowest first
+struct Neighbors(u32);
+
+impl Iterator for Neighbors {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let offset = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashers::FxBuildHasher;
+
+    #[test]
+    fn test_shared_home_slot() {
+        let mut hash_table = HopscotchTable::with_capacity_and_hasher(0, FxBuildHasher::default());
+
+        for i in 0..200u64 {
+            hash_table.insert(i, i);
+        }
+
+        // Under FxHash these keys all share home slot 0 (see `FxHasher`) and take
+        // over half its neighborhood, hopping others out of it
+        for i in 1..=16u64 {
+            assert_eq!(hash_table.insert(i << 32, i), None);
+        }
+
+        // Key 0 hashes to 0 as well
+        assert_eq!(hash_table.hop_info[0].count_ones(), 17);
+        assert_eq!(hash_table.len(), 216);
+        assert_eq!(hash_table.insert(1 << 32, 100), Some(1));
+        *hash_table.get_mut(&(2 << 32)).unwrap() = 200;
+
+        for i in 0..200u64 {
+            assert_eq!(hash_table.get(&i), Some(&i));
+        }
+        for i in 3..=16u64 {
+            assert_eq!(hash_table.get(&(i << 32)), Some(&i));
+        }
+        assert_eq!(hash_table.get(&(1 << 32)), Some(&100));
+        assert_eq!(hash_table.get(&(2 << 32)), Some(&200));
+    }
+
+    #[test]
+    fn test_borrowed_lookup_after_hop() {
+        let mut hash_table = HopscotchTable::with_len(64, FxBuildHasher::default());
+
+        // One key at each of the homes 0..H, plus a second key for home 0
+        let mut by_home: Vec<Vec<String>> = vec![Vec::new(); H];
+        for i in 0.. {
+            let key = format!("key_{i}");
+            let home = hash_table.create_index(key.as_str());
+            if home < H && by_home[home].len() < 2 {
+                by_home[home].push(key);
+            }
+            if by_home[0].len() == 2 && by_home.iter().all(|keys| !keys.is_empty()) {
+                break;
+            }
+        }
+
+        for keys in &by_home {
+            hash_table.insert(keys[0].clone(), keys[0].len());
+        }
+
+        // Slots 0..H are full, so the closest free slot is H away from home 0: the
+        // key homed at 1 hops out to slot H and the new key takes slot 1
+        hash_table.insert(by_home[0][1].clone(), 0);
+
+        let hopped = by_home[1][0].as_str();
+        assert_eq!(hash_table.find(hopped), Some(H));
+        assert_eq!(hash_table.get(hopped), Some(&hopped.len()));
+        assert_eq!(hash_table.find(by_home[0][1].as_str()), Some(1));
+        assert_eq!(hash_table.delete(hopped), Some(hopped.len()));
+        assert!(!hash_table.contains_key(hopped));
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut hash_table = HopscotchTable::new(100);
+
+        for i in 0..100 {
+            hash_table.insert(i, i);
+        }
+
+        for i in (0..100).step_by(3) {
+            assert_eq!(hash_table.delete(&i), Some(i));
+        }
+
+        assert_eq!(hash_table.delete(&0), None);
+
+        for i in 0..100 {
+            let expected = if i % 3 == 0 { None } else { Some(&i) };
+            assert_eq!(hash_table.get(&i), expected);
+        }
+    }
+
+    #[test]
+    fn test_neighborhood_invariant() {
+        let mut hash_table = HopscotchTable::with_len(128, RandomState::new());
+
+        // Up to the load factor limit, so plenty of entries had to hop
+        for i in 0..115 {
+            hash_table.insert(i, i);
+        }
+
+        for (index, slot) in hash_table.slots.iter().enumerate() {
+            if let Some(kv) = slot {
+                let home = hash_table.create_index(&kv.key);
+                let distance = hash_table.distance(home, index);
+
+                assert!(distance < H);
+                assert_ne!(hash_table.hop_info[home] & (1 << distance), 0);
+            }
+        }
+    }
+}
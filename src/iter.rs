@@ -0,0 +1,490 @@
+use std::iter::FusedIterator;
+
+use crate::raw::{RawDrain, RawIntoIter, RawIter, RawIterMut};
+use crate::{HashTable, KV};
+
+pub struct Iter<'a, K, V, H = ()> {
+    inner: RawIter<'a, KV<K, V, H>>,
+}
+
+pub struct IterMut<'a, K, V, H = ()> {
+    inner: RawIterMut<'a, KV<K, V, H>>,
+}
+
+pub struct IntoIter<K, V, H = ()> {
+    inner: RawIntoIter<KV<K, V, H>>,
+}
+
+// Owns the drained buckets; the table is already empty while this is alive
+pub struct Drain<'a, K, V, H = ()> {
+    inner: RawDrain<'a, KV<K, V, H>>,
+}
+
+pub struct ExtractIf<'a, K, V, S, F, H = (), O = ()> {
+    table: &'a mut HashTable<K, V, S, H, O>,
+    pred: F,
+    // Cursor over the old array's buckets followed by the new array's
+    bucket: usize,
+    position: usize,
+}
+
+pub struct Keys<'a, K, V, H = ()> {
+    inner: Iter<'a, K, V, H>,
+}
+
+pub struct Values<'a, K, V, H = ()> {
+    inner: Iter<'a, K, V, H>,
+}
+
+pub struct ValuesMut<'a, K, V, H = ()> {
+    inner: IterMut<'a, K, V, H>,
+}
+
+pub struct IntoKeys<K, V, H = ()> {
+    inner: IntoIter<K, V, H>,
+}
+
+pub struct IntoValues<K, V, H = ()> {
+    inner: IntoIter<K, V, H>,
+}
+
+impl<K, V, S, H, O> HashTable<K, V, S, H, O> {
+    pub fn iter(&self) -> Iter<'_, K, V, H> {
+        Iter {
+            inner: self.raw.iter(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V, H> {
+        IterMut {
+            inner: self.raw.iter_mut(),
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V, H> {
+        Keys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> Values<'_, K, V, H> {
+        Values { inner: self.iter() }
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V, H> {
+        ValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+
+    pub fn into_keys(self) -> IntoKeys<K, V, H> {
+        IntoKeys {
+            inner: self.into_iter(),
+        }
+    }
+
+    pub fn into_values(self) -> IntoValues<K, V, H> {
+        IntoValues {
+            inner: self.into_iter(),
+        }
+    }
+
+    // Empties the table up front, keeping the bucket array length
+    pub fn drain(&mut self) -> Drain<'_, K, V, H> {
+        Drain {
+            inner: self.raw.drain(),
+        }
+    }
+
+    // Lazily removes and yields the entries `pred` returns true for. Entries not
+    // reached before the iterator is dropped stay in the table.
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, K, V, S, F, H, O>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        ExtractIf {
+            table: self,
+            pred,
+            bucket: 0,
+            position: 0,
+        }
+    }
+}
+
+impl<K, V, S, H, O> IntoIterator for HashTable<K, V, S, H, O> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, H>;
+
+    fn into_iter(self) -> IntoIter<K, V, H> {
+        IntoIter {
+            inner: self.raw.into_iter(),
+        }
+    }
+}
+
+impl<'a, K, V, S, H, O> IntoIterator for &'a HashTable<K, V, S, H, O> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, H>;
+
+    fn into_iter(self) -> Iter<'a, K, V, H> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S, H, O> IntoIterator for &'a mut HashTable<K, V, S, H, O> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V, H>;
+
+    fn into_iter(self) -> IterMut<'a, K, V, H> {
+        self.iter_mut()
+    }
+}
+
+impl<'a, K, V, H> Iterator for Iter<'a, K, V, H> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|kv| (&kv.key, &kv.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V, H> Iterator for IterMut<'a, K, V, H> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|kv| (&kv.key, &mut kv.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V, H> Iterator for IntoIter<K, V, H> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|kv| (kv.key, kv.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V, H> Iterator for Drain<'_, K, V, H> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|kv| (kv.key, kv.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V, S, F, H, O> Iterator for ExtractIf<'_, K, V, S, F, H, O>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let pred = &mut self.pred;
+        let kv = self
+            .table
+            .raw
+            .extract_next(&mut self.bucket, &mut self.position, |kv| {
+                pred(&kv.key, &mut kv.value)
+            })?;
+
+        Some((kv.key, kv.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.table.raw.len()))
+    }
+}
+
+impl<'a, K, V, H> Iterator for Keys<'a, K, V, H> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V, H> Iterator for Values<'a, K, V, H> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V, H> Iterator for ValuesMut<'a, K, V, H> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<&'a mut V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V, H> Iterator for IntoKeys<K, V, H> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V, H> Iterator for IntoValues<K, V, H> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V, H> ExactSizeIterator for Iter<'_, K, V, H> {}
+impl<K, V, H> ExactSizeIterator for Drain<'_, K, V, H> {}
+impl<K, V, H> ExactSizeIterator for IterMut<'_, K, V, H> {}
+impl<K, V, H> ExactSizeIterator for IntoIter<K, V, H> {}
+impl<K, V, H> ExactSizeIterator for Keys<'_, K, V, H> {}
+impl<K, V, H> ExactSizeIterator for Values<'_, K, V, H> {}
+impl<K, V, H> ExactSizeIterator for ValuesMut<'_, K, V, H> {}
+impl<K, V, H> ExactSizeIterator for IntoKeys<K, V, H> {}
+impl<K, V, H> ExactSizeIterator for IntoValues<K, V, H> {}
+
+// The raw iterators keep returning None once done
+impl<K, V, H> FusedIterator for Iter<'_, K, V, H> {}
+impl<K, V, H> FusedIterator for Drain<'_, K, V, H> {}
+impl<K, V, H> FusedIterator for IterMut<'_, K, V, H> {}
+impl<K, V, H> FusedIterator for IntoIter<K, V, H> {}
+impl<K, V, H> FusedIterator for Keys<'_, K, V, H> {}
+impl<K, V, H> FusedIterator for Values<'_, K, V, H> {}
+impl<K, V, H> FusedIterator for ValuesMut<'_, K, V, H> {}
+impl<K, V, H> FusedIterator for IntoKeys<K, V, H> {}
+impl<K, V, H> FusedIterator for IntoValues<K, V, H> {}
+
+impl<K, V, H> Clone for Iter<'_, K, V, H> {
+    fn clone(&self) -> Self {
+        Iter {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use crate::{GrowthPolicy, HashTable, RehashMode};
+
+    fn sorted<T: Ord>(mut items: Vec<T>) -> Vec<T> {
+        items.sort();
+        items
+    }
+
+    #[test]
+    fn test_iter_family() {
+        let mut hash_table: HashTable<u64, u64> = HashTable::new(4);
+
+        for i in 0..10 {
+            hash_table.insert(i, i * 10);
+        }
+
+        let iter = hash_table.iter();
+        assert_eq!(iter.len(), 10);
+        assert_eq!(
+            sorted(iter.map(|(k, v)| (*k, *v)).collect()),
+            (0..10).map(|i| (i, i * 10)).collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            sorted(hash_table.keys().copied().collect()),
+            (0..10).collect::<Vec<_>>()
+        );
+        assert_eq!(hash_table.values().sum::<u64>(), 450);
+
+        for value in hash_table.values_mut() {
+            *value += 1;
+        }
+        for (key, value) in &mut hash_table {
+            *value += key;
+        }
+        for (key, value) in &hash_table {
+            assert_eq!(*value, key * 11 + 1);
+        }
+
+        assert_eq!(
+            sorted(hash_table.clone().into_keys().collect()),
+            (0..10).collect::<Vec<_>>()
+        );
+        assert_eq!(hash_table.into_values().len(), 10);
+    }
+
+    #[test]
+    fn test_exact_and_fused() {
+        let mut hash_table: HashTable<u64, u64> = HashTable::new(8);
+        hash_table.insert(1, 1);
+        hash_table.insert(2, 2);
+        hash_table.insert(1, 3);
+
+        let mut iter = hash_table.iter();
+        assert_eq!(iter.len(), 2);
+        iter.next();
+        assert_eq!(iter.len(), 1);
+        iter.next();
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut hash_table: HashTable<u64, u64> = HashTable::new(16);
+
+        for i in 0..10 {
+            hash_table.insert(i, i);
+        }
+
+        let mut drain = hash_table.drain();
+        assert_eq!(drain.len(), 10);
+        drain.next();
+        drop(drain);
+
+        assert_eq!(hash_table.size(), 0);
+        assert_eq!(hash_table.iter().next(), None);
+        assert_eq!(hash_table.raw.buckets.len(), 16);
+
+        hash_table.insert(1, 1);
+        assert_eq!(hash_table.get(&1), Some(&1));
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut hash_table: HashTable<u64, u64> = HashTable::new(4);
+
+        for i in 0..100 {
+            hash_table.insert(i, i);
+        }
+
+        hash_table.retain(|key, value| {
+            *value *= 2;
+            key % 3 == 0
+        });
+
+        assert_eq!(hash_table.size(), 34);
+        assert_eq!(hash_table.iter().len(), 34);
+        assert_eq!(hash_table.get(&99), Some(&198));
+        assert_eq!(hash_table.get(&98), None);
+    }
+
+    #[test]
+    fn test_extract_if() {
+        let mut hash_table: HashTable<u64, u64> = HashTable::new(4);
+
+        for i in 0..100 {
+            hash_table.insert(i, i);
+        }
+
+        let extracted = sorted(hash_table.extract_if(|key, _| key % 2 == 0).collect());
+
+        assert_eq!(
+            extracted,
+            (0..100).step_by(2).map(|i| (i, i)).collect::<Vec<_>>()
+        );
+        assert_eq!(hash_table.size(), 50);
+        assert_eq!(hash_table.iter().count(), 50);
+
+        // Dropping it early keeps whatever wasn't visited
+        assert_eq!(hash_table.extract_if(|_, _| true).take(10).count(), 10);
+        assert_eq!(hash_table.size(), 40);
+        assert_eq!(hash_table.iter().count(), 40);
+    }
+
+    #[test]
+    fn test_panicking_predicate() {
+        let mut hash_table: HashTable<u64, u64> = HashTable::new(4);
+
+        for i in 0..100 {
+            hash_table.insert(i, i);
+        }
+
+        let mut calls = 0;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            hash_table.retain(|_, _| {
+                calls += 1;
+                assert!(calls < 50);
+                calls % 2 == 0
+            })
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(hash_table.size(), hash_table.iter().count());
+
+        let survivor = *hash_table.keys().last().unwrap();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            hash_table
+                .extract_if(|key, _| {
+                    assert!(*key != survivor);
+                    true
+                })
+                .count()
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(hash_table.size(), hash_table.iter().count());
+        assert_eq!(hash_table.get(&survivor), Some(&survivor));
+    }
+
+    #[test]
+    fn test_iter_mid_rehash() {
+        let mut hash_table: HashTable<u64, u64> = HashTable::new(16);
+        hash_table.set_growth_policy(GrowthPolicy {
+            rehash: RehashMode::Incremental {
+                buckets_per_step: 1,
+            },
+            ..GrowthPolicy::default()
+        });
+
+        for i in 0..14 {
+            hash_table.insert(i, i);
+        }
+
+        assert!(hash_table.raw.rehashing.is_some());
+        assert_eq!(hash_table.iter().len(), 14);
+        assert_eq!(
+            sorted(hash_table.keys().copied().collect()),
+            (0..14).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            sorted(hash_table.into_keys().collect()),
+            (0..14).collect::<Vec<_>>()
+        );
+    }
+}
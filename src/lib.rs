@@ -1,6 +1,38 @@
-use std::collections::hash_map::DefaultHasher;
-use std::fmt::Debug;
-use std::hash::{Hash, Hasher};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+
+pub mod analysis;
+mod cache;
+pub mod cuckoo;
+mod entry;
+mod error;
+pub mod hashers;
+pub mod hopscotch;
+mod iter;
+pub mod open_addressing;
+mod order;
+mod policy;
+pub mod raw;
+pub mod robin_hood;
+pub mod stable;
+pub mod swiss;
+mod traits;
+mod tree_bin;
+
+pub use cache::HashCache;
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+pub use error::TryReserveError;
+pub use iter::{
+    Drain, ExtractIf, IntoIter, IntoKeys, IntoValues, Iter, IterMut, Keys, Values, ValuesMut,
+};
+pub use order::{ByKey, KeyOrder};
+pub use policy::{GrowthPolicy, IndexMode, RehashMode};
+
+use raw::{RawTable, Search};
+use tree_bin::Order;
 
 /*
     TODO:
@@ -8,111 +40,544 @@ use std::hash::{Hash, Hasher};
     - generics ✅
     - resize ✅
     - delete ✅
-    - impl iter
-    - handle collision
+    - impl iter ✅
+    - handle collision ✅
 */
 
-const DEFAULT_BUCKET_SIZE: usize = 100;
-
-type Bucket<K, V> = Option<KV<K, V>>;
-type Buckets<K, V> = Vec<Bucket<K, V>>;
-
 #[derive(Clone, Debug)]
-struct KV<K, V> {
+struct KV<K, V, H = ()> {
     key: K,
     value: V,
+    // Full hash of the key, when the table caches them
+    hash: H,
 }
 
-#[derive(Clone, Debug)]
-struct HashTable<K, V>
+impl<K, V> KV<K, V> {
+    // Entry without a cached hash, the way the open addressing tables store them
+    fn new(key: K, value: V) -> Self {
+        KV {
+            key,
+            value,
+            hash: (),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct HashTable<K, V, S = RandomState, H = (), O = ()> {
+    raw: RawTable<KV<K, V, H>>,
+    hash_builder: S,
+    order: PhantomData<O>,
+}
+
+/// A `HashTable` that stores every key's hash next to it, for keys that are
+/// expensive to hash or compare.
+pub type CachedHashTable<K, V, S = RandomState> = HashTable<K, V, S, u64>;
+
+/// A `HashTable` that sorts keys sharing a full hash by their `Ord` impl, so even
+/// keys crafted to collide on every hash bit are looked up in O(log n).
+pub type OrderedHashTable<K, V, S = RandomState> = HashTable<K, V, S, (), ByKey>;
+
+impl<K, V> HashTable<K, V, RandomState>
 where
-    K: Debug,
-    V: Debug,
+    K: Hash + Eq,
 {
-    buckets: Buckets<K, V>,
-    size: usize,
+    pub fn new(with_capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(with_capacity, RandomState::new())
+    }
 }
 
-impl<K, V> HashTable<K, V>
+impl<K, V, S, H, O> HashTable<K, V, S, H, O>
 where
-    K: Clone + Hash + Eq + Debug,
-    V: Clone + Debug,
+    K: Hash + Eq,
+    S: BuildHasher,
+    H: HashCache,
+    O: KeyOrder<K>,
 {
-    pub fn new(with_capacity: usize) -> Self {
-        let buckets: Buckets<K, V> = vec![None; with_capacity];
+    // Doesn't allocate until the first insert
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
 
-        HashTable { buckets, size: 0 }
+    pub fn with_capacity_and_hasher(with_capacity: usize, hash_builder: S) -> Self {
+        let mut raw = RawTable::with_buckets(with_capacity);
+        raw.order = entry_order::<K, V, H, O>();
+
+        HashTable {
+            raw,
+            hash_builder,
+            order: PhantomData,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn growth_policy(&self) -> &GrowthPolicy {
+        self.raw.growth_policy()
+    }
+
+    // A new index mode sends every entry to another bucket, so they're moved into
+    // a fresh bucket array laid out for it
+    pub fn set_growth_policy(&mut self, policy: GrowthPolicy) {
+        if policy.index == self.raw.growth_policy().index || self.is_empty() {
+            self.raw.set_growth_policy(policy);
+            return;
+        }
+
+        let mut raw = RawTable::new();
+        raw.set_growth_policy(policy);
+        raw.order = self.raw.order;
+        let old = std::mem::replace(&mut self.raw, raw);
+
+        let hasher = make_hasher(&self.hash_builder);
+        self.raw.reserve(old.len(), &hasher);
+        for kv in old {
+            let hash = hasher(&kv);
+            self.raw
+                .insert_unique(hash, kv, &hasher)
+                .unwrap_or_else(|err| err.handle());
+        }
     }
 
     pub fn size(&self) -> usize {
-        self.size
+        self.raw.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.raw.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.raw.is_empty()
+    }
+
+    // Number of entries the table holds before it has to grow
+    pub fn capacity(&self) -> usize {
+        self.raw.capacity()
+    }
+
+    // Gives back the value it replaced, if the key was already there
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.try_insert(key, value)
+            .unwrap_or_else(|err| err.handle())
+    }
+
+    // Like `insert`, but reports a failed allocation instead of aborting. On error the
+    // entries are left as they were and the key and value are dropped.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<Option<V>, TryReserveError> {
+        self.try_insert_with_hash(self.make_hash(&key), key, value)
     }
 
-    pub fn insert(&mut self, key: K, value: V) {
-        if self.size() + 1 > self.buckets.len() {
-            self.resize();
+    // `insert` with the key's hash already worked out by `self.hasher()`. Any other
+    // hash files the key where lookups won't find it.
+    pub fn insert_with_hash(&mut self, hash: u64, key: K, value: V) -> Option<V> {
+        self.try_insert_with_hash(hash, key, value)
+            .unwrap_or_else(|err| err.handle())
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        O: KeyOrder<K, Q>,
+    {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    // `get` with the key's hash already worked out by `self.hasher()`
+    pub fn get_with_hash<Q>(&self, hash: u64, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+        O: KeyOrder<K, Q>,
+    {
+        self.raw
+            .find_by(hash, &mut equivalent::<O, _>(hash, key))
+            .map(|kv| &kv.value)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        O: KeyOrder<K, Q>,
+    {
+        let hash = self.make_hash(key);
+        self.raw
+            .find_by(hash, &mut equivalent::<O, _>(hash, key))
+            .map(|kv| (&kv.key, &kv.value))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        O: KeyOrder<K, Q>,
+    {
+        let hash = self.make_hash(key);
+        self.raw
+            .find_mut_by(hash, &mut equivalent::<O, _>(hash, key))
+            .map(|kv| &mut kv.value)
+    }
+
+    // Mutable references to the values of `N` keys at once. None if any key is missing
+    // or the same key is asked for twice.
+    pub fn get_many_mut<Q, const N: usize>(&mut self, keys: [&Q; N]) -> Option<[&mut V; N]>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        O: KeyOrder<K, Q>,
+    {
+        let slots = self.locate_many(keys)?;
+
+        for (i, slot) in slots.iter().enumerate() {
+            if slots[..i].contains(slot) {
+                return None;
+            }
+        }
+
+        // SAFETY: the slots were just located and checked to be pairwise distinct
+        let kvs = unsafe { self.raw.slots_mut(slots) };
+        Some(kvs.map(|kv| &mut kv.value))
+    }
+
+    /// Like `get_many_mut`, without checking that the keys are distinct.
+    ///
+    /// # Safety
+    ///
+    /// No two keys may be equal, otherwise the returned references alias.
+    pub unsafe fn get_many_unchecked_mut<Q, const N: usize>(
+        &mut self,
+        keys: [&Q; N],
+    ) -> Option<[&mut V; N]>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        O: KeyOrder<K, Q>,
+    {
+        let slots = self.locate_many(keys)?;
+
+        // SAFETY: distinct keys live in distinct slots, which the caller vouches for
+        let kvs = unsafe { self.raw.slots_mut(slots) };
+        Some(kvs.map(|kv| &mut kv.value))
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        O: KeyOrder<K, Q>,
+    {
+        self.get_key_value(key).is_some()
+    }
+
+    // Same as `remove`, matching the open addressing tables' `delete`
+    pub fn delete<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        O: KeyOrder<K, Q>,
+    {
+        self.remove(key)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        O: KeyOrder<K, Q>,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        O: KeyOrder<K, Q>,
+    {
+        let hash = self.make_hash(key);
+        let hasher = make_hasher(&self.hash_builder);
+        self.raw.write_step(&hasher);
+
+        let kv = self
+            .raw
+            .remove_entry_by(hash, &mut equivalent::<O, _>(hash, key))?;
+        self.raw.shrink_if_sparse(&hasher);
+
+        Some((kv.key, kv.value))
+    }
+
+    // Keeps only the entries `f` returns true for, in a single pass over the buckets
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.raw.retain(|kv| f(&kv.key, &mut kv.value));
+        self.raw.shrink_if_sparse(&make_hasher(&self.hash_builder));
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S, H, O> {
+        let hash = self.make_hash(&key);
+        {
+            let hasher = make_hasher(&self.hash_builder);
+            self.raw.write_step(&hasher);
+            self.raw.migrate_bucket_of(hash, &hasher);
+        }
+
+        match self.raw.locate(hash, &mut equivalent::<O, _>(hash, &key)) {
+            Some((index, position)) => Entry::Occupied(OccupiedEntry::new(self, index, position)),
+            // Also where an unallocated table ends up; the vacant insert allocates
+            None => Entry::Vacant(VacantEntry::new(self, key, hash)),
+        }
+    }
+
+    // Makes room for `additional` more entries without growing on the way
+    pub fn reserve(&mut self, additional: usize) {
+        self.try_reserve(additional)
+            .unwrap_or_else(|err| err.handle())
+    }
+
+    // Like `reserve`, but reports a failed allocation instead of aborting
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.raw
+            .try_reserve(additional, make_hasher(&self.hash_builder))
+    }
+
+    // Shrinks the bucket array as far as the entries and `min_capacity` allow
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.raw
+            .shrink_to(min_capacity, make_hasher(&self.hash_builder));
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+    fn try_insert_with_hash(
+        &mut self,
+        hash: u64,
+        key: K,
+        value: V,
+    ) -> Result<Option<V>, TryReserveError> {
+        let hasher = make_hasher(&self.hash_builder);
+        self.raw.write_step(&hasher);
+
+        if let Some(kv) = self
+            .raw
+            .find_mut_by(hash, &mut equivalent::<O, _>(hash, &key))
+        {
+            return Ok(Some(std::mem::replace(&mut kv.value, value)));
         }
 
         let kv = KV {
-            key: key.clone(),
+            key,
             value,
+            hash: H::store(hash),
         };
+        self.raw.insert_unique(hash, kv, &hasher)?;
+        Ok(None)
+    }
 
-        let index = self.create_index(key);
-
-        self.buckets[index] = Some(kv);
-        self.size += 1;
+    // Adds a key known to be absent, returns where it landed
+    fn insert_unique(&mut self, hash: u64, key: K, value: V) -> (usize, usize) {
+        let kv = KV {
+            key,
+            value,
+            hash: H::store(hash),
+        };
+        self.raw
+            .insert_unique(hash, kv, &make_hasher(&self.hash_builder))
+            .unwrap_or_else(|err| err.handle())
     }
 
-    pub fn get(&self, key: K) -> Option<V> {
-        let index = self.create_index(key.clone());
-        self.buckets[index]
-            .clone()
-            .and_then(|kv| if kv.key == key { Some(kv.value) } else { None })
+    fn remove_at(&mut self, index: usize, position: usize) -> KV<K, V, H> {
+        let kv = self.raw.remove_at(index, position);
+        self.raw.shrink_if_sparse(&make_hasher(&self.hash_builder));
+
+        kv
     }
 
-    pub fn delete(&mut self, key: K) {
-        let index = self.create_index(key);
-        self.buckets[index] = None;
+    // Slots in the new array; every key's old bucket is migrated before any is located
+    fn locate_many<Q, const N: usize>(&mut self, keys: [&Q; N]) -> Option<[(usize, usize); N]>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        O: KeyOrder<K, Q>,
+    {
+        let hashes = keys.map(|key| self.make_hash(key));
+        let hasher = make_hasher(&self.hash_builder);
+
+        for &hash in &hashes {
+            self.raw.migrate_bucket_of(hash, &hasher);
+        }
+
+        let mut slots = [(0, 0); N];
+        for (slot, (key, hash)) in slots.iter_mut().zip(keys.into_iter().zip(hashes)) {
+            *slot = self.raw.locate(hash, &mut equivalent::<O, _>(hash, key))?;
+        }
+
+        Some(slots)
     }
 
-    fn create_index(&self, key: K) -> usize {
-        let mut s = DefaultHasher::new();
-        key.hash(&mut s);
-        let hash = s.finish();
+    fn make_hash<Q>(&self, key: &Q) -> u64
+    where
+        Q: Hash + ?Sized,
+    {
+        self.hash_builder.hash_one(key)
+    }
+}
 
-        // Modulo arithmetic -> Uniform Distribution
-        (hash % (self.buckets.len() as u64)) as usize
+// Hash of a stored entry, for moving it into a resized bucket array
+fn make_hasher<K, V, S, H>(hash_builder: &S) -> impl Fn(&KV<K, V, H>) -> u64 + '_
+where
+    K: Hash,
+    S: BuildHasher,
+    H: HashCache,
+{
+    move |kv| {
+        kv.hash
+            .load()
+            .unwrap_or_else(|| hash_builder.hash_one(&kv.key))
     }
+}
 
-    fn resize(&mut self) {
-        let old_buckets = self.buckets.clone();
-        self.buckets = vec![None; old_buckets.len() + DEFAULT_BUCKET_SIZE];
+// How tree bins sort entries sharing a full hash, if `O` orders keys at all
+fn entry_order<K, V, H, O: KeyOrder<K>>() -> Order<KV<K, V, H>> {
+    O::ORDERED.then_some(|a, b| O::compare(&a.key, &b.key))
+}
 
-        for bucket in old_buckets {
-            if let Some(bucket) = bucket {
-                self.insert(bucket.key, bucket.value);
-            }
-        }
+// Matches the entry holding `key`; a cached hash rules out most others before `Eq`
+// runs. Tables ordering their keys binary search tree bins with `O`.
+struct Equivalent<'a, Q: ?Sized, O> {
+    hash: u64,
+    key: &'a Q,
+    order: PhantomData<O>,
+}
+
+fn equivalent<O, Q: ?Sized>(hash: u64, key: &Q) -> Equivalent<'_, Q, O> {
+    Equivalent {
+        hash,
+        key,
+        order: PhantomData,
     }
 }
 
-impl<K, V> Iterator for HashTable<K, V>
+impl<K, V, H, Q, O> Search<KV<K, V, H>> for Equivalent<'_, Q, O>
 where
-    K: Clone + Hash + Eq + Debug,
-    V: Clone + Debug,
+    K: Borrow<Q>,
+    Q: Eq + ?Sized,
+    H: HashCache,
+    O: KeyOrder<K, Q>,
 {
-    type Item = (K, V);
+    const ORDERED: bool = O::ORDERED;
+
+    fn eq(&mut self, kv: &KV<K, V, H>) -> bool {
+        kv.hash.matches(self.hash) && kv.key.borrow() == self.key
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        todo!()
+    fn cmp(&mut self, kv: &KV<K, V, H>) -> Ordering {
+        O::compare(self.key, &kv.key)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::cell::Cell;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{BuildHasherDefault, Hasher};
+
     use super::*;
 
+    thread_local! {
+        static HASH_CALLS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    thread_local! {
+        static EQ_CALLS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    thread_local! {
+        static CMP_CALLS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    // Hashes to its number shifted past the bits a power-of-two table indexes with,
+    // so every key lands in bucket 0. Numbers that only differ above their low 24 bits
+    // share the full hash too. Counts its `Eq` and `Ord` calls.
+    #[derive(Debug)]
+    struct CollidingKey(u64);
+
+    impl Hash for CollidingKey {
+        fn hash<T: Hasher>(&self, state: &mut T) {
+            state.write_u64(self.0 << 40);
+        }
+    }
+
+    impl PartialEq for CollidingKey {
+        fn eq(&self, other: &Self) -> bool {
+            EQ_CALLS.with(|calls| calls.set(calls.get() + 1));
+            self.0 == other.0
+        }
+    }
+
+    impl Eq for CollidingKey {}
+
+    impl PartialOrd for CollidingKey {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for CollidingKey {
+        fn cmp(&self, other: &Self) -> Ordering {
+            CMP_CALLS.with(|calls| calls.set(calls.get() + 1));
+            self.0.cmp(&other.0)
+        }
+    }
+
+    // Hands back the last written integer as the hash, with no seed at all. Other
+    // writes are just folded in, byte by byte.
+    #[derive(Default)]
+    struct IdentityHasher(u64);
+
+    impl Hasher for IdentityHasher {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for &byte in bytes {
+                self.0 = self.0.rotate_left(8) ^ u64::from(byte);
+            }
+        }
+
+        fn write_u64(&mut self, n: u64) {
+            self.0 = n;
+        }
+    }
+
+    // Counts how often it gets hashed, on the current test's thread
+    #[derive(PartialEq, Eq)]
+    struct CountedKey(u64);
+
+    impl Hash for CountedKey {
+        fn hash<T: Hasher>(&self, state: &mut T) {
+            HASH_CALLS.with(|calls| calls.set(calls.get() + 1));
+            self.0.hash(state);
+        }
+    }
+
+    fn index_of<K, V, S, Q>(hash_table: &HashTable<K, V, S>, key: &Q) -> usize
+    where
+        K: Hash + Eq,
+        S: BuildHasher,
+        Q: Hash + ?Sized,
+    {
+        hash_table.raw.create_index(hash_table.make_hash(key))
+    }
+
     #[test]
     fn test_new() {
         let hash_table: HashTable<String, u64> = HashTable::new(100);
@@ -129,8 +594,8 @@ mod tests {
 
         assert_eq!(hash_table.size(), 3);
 
-        assert_eq!(hash_table.get("key1".to_string()), Some(1));
-        assert_eq!(hash_table.get("key3".to_string()), Some(3));
+        assert_eq!(hash_table.get("key1"), Some(&1));
+        assert_eq!(hash_table.get("key3"), Some(&3));
     }
 
     #[test]
@@ -141,11 +606,218 @@ mod tests {
         hash_table.insert("key2".to_string(), 2);
         hash_table.insert("key3".to_string(), 3);
 
-        assert_eq!(hash_table.get("key2".to_string()), Some(2));
+        assert_eq!(hash_table.get("key2"), Some(&2));
+
+        assert_eq!(hash_table.delete("key2"), Some(2));
+
+        assert_eq!(hash_table.get("key2"), None);
+        assert_eq!(hash_table.delete("key2"), None);
+    }
+
+    #[test]
+    fn test_insert_remove_return_values() {
+        let mut hash_table: HashTable<String, u64> = HashTable::new(10);
+
+        assert!(hash_table.is_empty());
+        assert_eq!(hash_table.insert("key1".to_string(), 1), None);
+        assert_eq!(hash_table.insert("key1".to_string(), 2), Some(1));
+        assert_eq!(hash_table.len(), 1);
+
+        assert_eq!(hash_table.remove("key1"), Some(2));
+        assert_eq!(hash_table.remove("key1"), None);
+        assert!(hash_table.is_empty());
+
+        hash_table.insert("key2".to_string(), 3);
+        assert_eq!(
+            hash_table.remove_entry("key2"),
+            Some(("key2".to_string(), 3))
+        );
+        assert_eq!(hash_table.len(), 0);
+    }
+
+    #[test]
+    fn test_len_matches_reference_model() {
+        let mut hash_table: HashTable<u64, u64> = HashTable::new(1);
+        hash_table.set_growth_policy(GrowthPolicy {
+            rehash: RehashMode::Incremental {
+                buckets_per_step: 1,
+            },
+            min_load_factor: Some(0.1),
+            ..GrowthPolicy::default()
+        });
+        let mut model = std::collections::HashMap::new();
+
+        // xorshift64, fixed seed so failures reproduce
+        let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..20_000 {
+            let key = next() % 500;
+
+            match next() % 4 {
+                0 | 1 => assert_eq!(hash_table.insert(key, key), model.insert(key, key)),
+                2 => assert_eq!(hash_table.remove(&key), model.remove(&key)),
+                _ => assert_eq!(hash_table.get(&key), model.get(&key)),
+            }
+
+            assert_eq!(hash_table.len(), model.len());
+        }
+
+        assert_eq!(hash_table.iter().len(), model.len());
+        assert_eq!(hash_table.iter().count(), model.len());
+    }
+
+    #[test]
+    fn test_collision() {
+        let mut hash_table: HashTable<String, u64> = HashTable::new(10);
+
+        let first = "key_0".to_string();
+        let colliding = (1..)
+            .map(|i| format!("key_{}", i))
+            .find(|key| index_of(&hash_table, key) == index_of(&hash_table, &first))
+            .unwrap();
+
+        hash_table.insert(first.clone(), 1);
+        hash_table.insert(colliding.clone(), 2);
+
+        assert_eq!(hash_table.get(&first), Some(&1));
+        assert_eq!(hash_table.get(&colliding), Some(&2));
+
+        hash_table.delete(&first);
+
+        assert_eq!(hash_table.get(&first), None);
+        assert_eq!(hash_table.get(&colliding), Some(&2));
+    }
+
+    #[test]
+    fn test_borrowed_lookups() {
+        let mut hash_table: HashTable<String, Vec<u64>> = HashTable::new(10);
+
+        hash_table.insert("key1".to_string(), vec![1]);
+
+        assert!(hash_table.contains_key("key1"));
+        assert!(!hash_table.contains_key("key2"));
+        assert_eq!(
+            hash_table.get_key_value("key1"),
+            Some((&"key1".to_string(), &vec![1]))
+        );
+
+        hash_table.get_mut("key1").unwrap().push(2);
+
+        assert_eq!(hash_table.get("key1"), Some(&vec![1, 2]));
+        assert_eq!(hash_table.get_mut("key2"), None);
+    }
+
+    #[test]
+    fn test_get_many_mut() {
+        let mut hash_table: HashTable<String, i64> = HashTable::new(1);
+        hash_table.set_growth_policy(GrowthPolicy {
+            rehash: RehashMode::Incremental {
+                buckets_per_step: 1,
+            },
+            ..GrowthPolicy::default()
+        });
+
+        for i in 0..50 {
+            hash_table.insert(format!("account_{}", i), 100);
+        }
+
+        // Some accounts may still sit in the old array of the last resize
+        let [from, to] = hash_table
+            .get_many_mut(["account_3", "account_42"])
+            .unwrap();
+        *from -= 30;
+        *to += 30;
+
+        assert_eq!(hash_table.get("account_3"), Some(&70));
+        assert_eq!(hash_table.get("account_42"), Some(&130));
+
+        assert!(hash_table
+            .get_many_mut(["account_3", "account_3"])
+            .is_none());
+        assert!(hash_table
+            .get_many_mut(["account_3", "account_99"])
+            .is_none());
+        assert_eq!(hash_table.get_many_mut::<str, 0>([]), Some([]));
+
+        // SAFETY: the keys are distinct
+        let values = unsafe { hash_table.get_many_unchecked_mut(["account_0", "account_1"]) };
+        assert_eq!(values.map(|[a, b]| *a + *b), Some(200));
+    }
+
+    #[test]
+    fn test_with_hasher() {
+        // Deterministic hasher: bucket placement is the same on every run
+        let hash_builder = BuildHasherDefault::<DefaultHasher>::default();
+        let mut hash_table = HashTable::with_capacity_and_hasher(10, hash_builder);
+
+        hash_table.insert("key1".to_string(), 1);
+        hash_table.insert("key2".to_string(), 2);
+
+        let expected = hash_table.hasher().hash_one("key1".to_string()) % 10;
+        assert_eq!(index_of(&hash_table, "key1"), expected as usize);
+
+        assert_eq!(hash_table.get("key1"), Some(&1));
+        assert_eq!(hash_table.get("key2"), Some(&2));
+    }
+
+    #[test]
+    fn test_with_hash() {
+        let mut hash_table: HashTable<String, u64> = HashTable::new(0);
+
+        // Hashed once, used for both the insert and the lookups
+        let hash = hash_table.hasher().hash_one("key1");
+        assert_eq!(
+            hash_table.insert_with_hash(hash, "key1".to_string(), 1),
+            None
+        );
+        assert_eq!(
+            hash_table.insert_with_hash(hash, "key1".to_string(), 2),
+            Some(1)
+        );
+
+        assert_eq!(hash_table.get_with_hash(hash, "key1"), Some(&2));
+        assert_eq!(hash_table.get("key1"), Some(&2));
+        assert_eq!(hash_table.size(), 1);
+    }
+
+    #[test]
+    fn test_cached_hashes() {
+        fn hash_calls<H: HashCache>(n: u64) -> usize {
+            let mut hash_table: HashTable<CountedKey, u64, RandomState, H> =
+                HashTable::with_capacity_and_hasher(1, RandomState::new());
+
+            HASH_CALLS.with(|calls| calls.set(0));
+            for i in 0..n {
+                hash_table.insert(CountedKey(i), i);
+            }
+            HASH_CALLS.with(Cell::get)
+        }
+
+        // The cache only costs space when asked for
+        assert_eq!(size_of::<KV<u32, u32>>(), 8);
+        assert_eq!(size_of::<KV<u32, u32, u64>>(), 16);
+
+        // Every resize hashes all keys again, unless their hashes are kept
+        assert!(hash_calls::<()>(1000) > 2000);
+        assert_eq!(hash_calls::<u64>(1000), 1000);
 
-        hash_table.delete("key2".to_string());
+        let mut hash_table: CachedHashTable<String, u64> = CachedHashTable::default();
 
-        assert_eq!(hash_table.get("key2".to_string()), None);
+        for i in 0..100 {
+            *hash_table.entry(format!("key_{}", i)).or_default() += i;
+        }
+
+        assert_eq!(hash_table.insert("key_1".to_string(), 10), Some(1));
+        assert_eq!(hash_table.remove("key_2"), Some(2));
+        assert_eq!(hash_table.get("key_1"), Some(&10));
+        assert_eq!(hash_table.get("key_2"), None);
+        assert_eq!(hash_table.size(), 99);
     }
 
     #[test]
@@ -154,16 +826,28 @@ mod tests {
         hash_table.insert("key_1".to_string(), "value1".to_string());
         hash_table.insert("key_2".to_string(), "value2".to_string());
 
-        let iter = hash_table.clone().into_iter();
+        let mut items: Vec<_> = hash_table.clone().into_iter().collect();
+        items.sort();
+
+        assert_eq!(
+            items,
+            vec![
+                ("key_1".to_string(), "value1".to_string()),
+                ("key_2".to_string(), "value2".to_string()),
+            ]
+        );
+
+        let mut keys: Vec<_> = hash_table.keys().collect();
+        keys.sort();
 
-        todo!()
+        assert_eq!(keys, vec!["key_1", "key_2"]);
     }
 
     #[test]
     fn test_resize() {
         let mut hash_table: HashTable<String, u64> = HashTable::new(3);
 
-        assert_eq!(hash_table.buckets.len(), 3);
+        assert_eq!(hash_table.raw.buckets.len(), 3);
 
         hash_table.insert("key_1".to_string(), 1);
         hash_table.insert("key_22".to_string(), 2);
@@ -171,11 +855,370 @@ mod tests {
 
         hash_table.insert("key_4".to_string(), 4);
 
-        assert_eq!(hash_table.buckets.len(), 103);
+        assert_eq!(hash_table.raw.buckets.len(), 6);
+
+        assert_eq!(hash_table.get("key_1"), Some(&1));
+        assert_eq!(hash_table.get("key_22"), Some(&2));
+        assert_eq!(hash_table.get("key_33"), Some(&3));
+        assert_eq!(hash_table.get("key_4"), Some(&4));
+    }
+
+    #[test]
+    fn test_growth_policy() {
+        let mut hash_table: HashTable<u64, u64> = HashTable::new(10);
+        hash_table.set_growth_policy(GrowthPolicy {
+            max_load_factor: 1.0,
+            growth_factor: 3.0,
+            power_of_two: true,
+            ..GrowthPolicy::default()
+        });
+
+        for i in 0..11 {
+            hash_table.insert(i, i);
+        }
+
+        // 10 * 3 rounded up to a power of two
+        assert_eq!(hash_table.raw.buckets.len(), 32);
+        assert_eq!(hash_table.capacity(), 32);
+    }
+
+    #[test]
+    fn test_reserve() {
+        let mut hash_table: HashTable<u64, u64> = HashTable::new(4);
+        hash_table.reserve(100);
+
+        let buckets = hash_table.raw.buckets.len();
+        assert!(hash_table.capacity() >= 100);
+
+        for i in 0..100 {
+            hash_table.insert(i, i);
+        }
+
+        assert_eq!(hash_table.raw.buckets.len(), buckets);
+    }
+
+    #[test]
+    fn test_shrink() {
+        let mut hash_table: HashTable<u64, u64> = HashTable::new(1000);
+
+        for i in 0..30 {
+            hash_table.insert(i, i);
+        }
+
+        hash_table.shrink_to(60);
+        assert_eq!(hash_table.raw.buckets.len(), 80);
+
+        hash_table.shrink_to_fit();
+        assert_eq!(hash_table.raw.buckets.len(), 40);
+
+        // Never below what the entries need
+        hash_table.shrink_to(0);
+        assert_eq!(hash_table.raw.buckets.len(), 40);
+
+        for i in 0..30 {
+            assert_eq!(hash_table.get(&i), Some(&i));
+        }
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let mut hash_table: HashTable<String, u64> = HashTable::new(0);
+
+        assert_eq!(hash_table.raw.buckets.capacity(), 0);
+        assert_eq!(hash_table.get("key1"), None);
+        assert_eq!(hash_table.get_mut("key1"), None);
+        assert_eq!(hash_table.remove("key1"), None);
+        assert_eq!(hash_table.iter().count(), 0);
+
+        *hash_table.entry("key1".to_string()).or_insert(0) += 1;
+        assert_eq!(hash_table.insert("key2".to_string(), 2), None);
+        assert_eq!(hash_table.get("key1"), Some(&1));
+
+        hash_table.retain(|_, _| false);
+        hash_table.shrink_to_fit();
+        assert_eq!(hash_table.raw.buckets.len(), 0);
+        assert_eq!(hash_table.get("key2"), None);
+
+        let default: HashTable<u64, u64> = HashTable::default();
+        assert_eq!(default.capacity(), 0);
+    }
+
+    #[test]
+    fn test_try_reserve() {
+        let mut hash_table: HashTable<u64, u64> = HashTable::new(0);
+
+        assert_eq!(
+            hash_table.try_reserve(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+
+        // Small enough for a valid layout, far too big for any allocator
+        assert!(matches!(
+            hash_table.try_reserve(1 << 55),
+            Err(TryReserveError::AllocError { .. })
+        ));
+
+        // Failed reservations leave the table usable
+        assert_eq!(hash_table.raw.buckets.len(), 0);
+        assert_eq!(hash_table.try_insert(1, 1), Ok(None));
+        assert_eq!(hash_table.try_insert(1, 2), Ok(Some(1)));
+
+        assert_eq!(hash_table.try_reserve(100), Ok(()));
+        assert!(hash_table.capacity() >= 101);
+    }
+
+    #[test]
+    fn test_auto_shrink() {
+        let mut hash_table: HashTable<u64, u64> = HashTable::new(0);
+        hash_table.set_growth_policy(GrowthPolicy {
+            min_load_factor: Some(0.25),
+            ..GrowthPolicy::default()
+        });
+
+        for i in 0..1000 {
+            hash_table.insert(i, i);
+        }
+
+        let grown = hash_table.raw.buckets.len();
+
+        for i in 0..990 {
+            hash_table.delete(&i);
+        }
+
+        assert_eq!(hash_table.size(), 10);
+        assert!(hash_table.raw.buckets.len() < grown / 10);
+        assert!(hash_table.raw.buckets.len() as f64 * 0.25 <= 10.0);
+
+        for i in 990..1000 {
+            assert_eq!(hash_table.get(&i), Some(&i));
+        }
+    }
+
+    #[test]
+    fn test_incremental_rehash() {
+        let mut hash_table: HashTable<u64, u64> = HashTable::new(16);
+        hash_table.set_growth_policy(GrowthPolicy {
+            rehash: RehashMode::Incremental {
+                buckets_per_step: 2,
+            },
+            ..GrowthPolicy::default()
+        });
+
+        for i in 0..=12 {
+            hash_table.insert(i, i);
+        }
+
+        // The 13th insert crossed 0.75 load: both arrays are live now
+        assert_eq!(hash_table.raw.buckets.len(), 32);
+        assert_eq!(hash_table.raw.rehashing.as_ref().map(|r| r.next), Some(0));
+
+        for i in 13..20 {
+            let next = hash_table.raw.rehashing.as_ref().unwrap().next;
+            hash_table.insert(i, i);
+            assert_eq!(hash_table.raw.rehashing.as_ref().unwrap().next, next + 2);
+
+            for j in 0..=i {
+                assert_eq!(hash_table.get(&j), Some(&j));
+            }
+        }
+
+        hash_table.delete(&0);
+
+        assert!(hash_table.raw.rehashing.is_none());
+        assert_eq!(hash_table.get(&0), None);
+        for i in 1..20 {
+            assert_eq!(hash_table.get(&i), Some(&i));
+        }
+    }
+
+    #[test]
+    fn test_adversarial_collisions() {
+        let n = 20_000;
+
+        for rehash in [
+            RehashMode::Blocking,
+            RehashMode::Incremental {
+                buckets_per_step: 1,
+            },
+        ] {
+            let mut hash_table: HashTable<CollidingKey, u64, BuildHasherDefault<IdentityHasher>> =
+                HashTable::default();
+            hash_table.set_growth_policy(GrowthPolicy {
+                power_of_two: true,
+                rehash,
+                ..GrowthPolicy::default()
+            });
+
+            for i in 0..n {
+                hash_table.insert(CollidingKey(i), i);
+            }
+
+            // Every key sits in one bucket, which got a tree bin
+            assert_eq!(index_of(&hash_table, &CollidingKey(n - 1)), 0);
+            assert!(hash_table.raw.tree_bins.contains_key(&0));
+
+            // One `Eq` per hit and none for a miss, where a plain chain needs ~n/2
+            EQ_CALLS.with(|calls| calls.set(0));
+            for i in 0..n {
+                assert_eq!(hash_table.get(&CollidingKey(i)), Some(&i));
+            }
+            assert_eq!(hash_table.get(&CollidingKey(n)), None);
+            assert_eq!(EQ_CALLS.with(Cell::get), n as usize);
+
+            // Shrinks back into a plain chain
+            for i in 3..n / 2 {
+                assert_eq!(hash_table.remove(&CollidingKey(i)), Some(i));
+            }
+            let extracted = hash_table.extract_if(|key, _| key.0 >= n / 2).count();
+            assert_eq!(extracted as u64, n / 2);
+            hash_table.retain(|key, _| key.0 != 0);
+
+            assert!(hash_table.raw.tree_bins.is_empty());
+            assert_eq!(hash_table.get(&CollidingKey(1)), Some(&1));
+            assert_eq!(hash_table.get(&CollidingKey(2)), Some(&2));
+            assert_eq!(hash_table.size(), 2);
+
+            // Keys sharing the full hash as well: a plain tree bin calls `Eq` on ~m/2
+            // of them per lookup, an ordered one binary searches them by key
+            let m = 4096;
+            let shared = |i: u64| CollidingKey((i + 1) << 24);
+            let mut hash_table: OrderedHashTable<
+                CollidingKey,
+                u64,
+                BuildHasherDefault<IdentityHasher>,
+            > = HashTable::default();
+            hash_table.set_growth_policy(GrowthPolicy {
+                power_of_two: true,
+                rehash,
+                ..GrowthPolicy::default()
+            });
+
+            for i in 0..m {
+                hash_table.insert(CollidingKey(i), i);
+                hash_table.insert(shared(i), i);
+            }
+            assert_eq!(hash_table.make_hash(&shared(m - 1)), 0);
+            assert!(hash_table.raw.tree_bins.contains_key(&0));
+
+            EQ_CALLS.with(|calls| calls.set(0));
+            CMP_CALLS.with(|calls| calls.set(0));
+            for i in 0..m {
+                assert_eq!(hash_table.get(&shared(i)), Some(&i));
+            }
+            assert_eq!(hash_table.get(&shared(m)), None);
+            assert_eq!(EQ_CALLS.with(Cell::get), m as usize);
+            // A binary search over the m + 1 keys hashing to 0 per lookup
+            let per_lookup = (m + 1).ilog2() as usize + 2;
+            assert!(CMP_CALLS.with(Cell::get) <= (m as usize + 1) * per_lookup);
+
+            // Removing keeps the runs sorted for the keys left
+            for i in (0..m).step_by(2) {
+                assert_eq!(hash_table.remove(&shared(i)), Some(i));
+            }
+            for i in 0..m {
+                let expected = (i % 2 == 1).then_some(i);
+                assert_eq!(hash_table.get(&shared(i)).copied(), expected);
+                assert_eq!(hash_table.get(&CollidingKey(i)), Some(&i));
+            }
+
+            hash_table.retain(|key, _| key.0 < 4);
+            assert!(hash_table.raw.tree_bins.is_empty());
+            assert_eq!(hash_table.size(), 4);
+        }
+    }
+
+    #[test]
+    fn test_per_table_seed() {
+        let first: HashTable<u64, u64> = HashTable::new(0);
+        let second: HashTable<u64, u64> = HashTable::new(0);
+
+        // Every table hashes with its own random keys, so collisions found against
+        // one don't carry over to another
+        assert_ne!(first.make_hash(&1), second.make_hash(&1));
+    }
+
+    #[test]
+    fn test_index_modes() {
+        let modes = [
+            (IndexMode::Modulo, false),
+            (IndexMode::MultiplyShift, true),
+            (IndexMode::FastRange, false),
+        ];
+
+        for (index, power_of_two) in modes {
+            let mut hash_table: HashTable<u64, u64> = HashTable::new(10);
+            hash_table.set_growth_policy(GrowthPolicy {
+                power_of_two,
+                index,
+                ..GrowthPolicy::default()
+            });
+
+            for i in 0..1000 {
+                hash_table.insert(i, i);
+            }
+
+            // Uniform hashes stay uniform: no bucket gets far more than the average
+            let buckets = &hash_table.raw.buckets;
+            let longest = buckets.iter().map(Vec::len).max().unwrap();
+            assert!(longest <= 10, "{:?}: chain of {}", index, longest);
+
+            // Switching back rebuilds the table under the new mapping
+            hash_table.set_growth_policy(GrowthPolicy::default());
+            assert_eq!(hash_table.len(), 1000);
+            for i in 0..1000 {
+                assert_eq!(hash_table.get(&i), Some(&i));
+            }
+        }
+
+        // Multiply-shift spreads keys that only differ in their high bits, which a
+        // power of two modulo would put in one bucket
+        let mut hash_table: HashTable<u64, u64, BuildHasherDefault<IdentityHasher>> =
+            HashTable::default();
+        hash_table.set_growth_policy(GrowthPolicy {
+            power_of_two: true,
+            index: IndexMode::MultiplyShift,
+            ..GrowthPolicy::default()
+        });
+
+        for i in 0..64 {
+            hash_table.insert(i << 32, i);
+        }
+
+        let longest = hash_table.raw.buckets.iter().map(Vec::len).max().unwrap();
+        assert!(longest <= 4, "chain of {}", longest);
+    }
+
+    #[test]
+    #[should_panic(expected = "multiply-shift indexing needs power of two bucket counts")]
+    fn test_multiply_shift_needs_power_of_two() {
+        let mut hash_table: HashTable<u64, u64> = HashTable::new(10);
+        hash_table.set_growth_policy(GrowthPolicy {
+            index: IndexMode::MultiplyShift,
+            ..GrowthPolicy::default()
+        });
+    }
+
+    #[test]
+    fn test_million_inserts_linear() {
+        let n = 1_000_000;
+        let mut hash_table: HashTable<u64, u64> = HashTable::new(0);
+
+        let mut resizes = 0;
+        let mut rehashed = 0;
+
+        for i in 0..n {
+            let capacity = hash_table.capacity();
+            hash_table.insert(i, i);
+
+            if hash_table.capacity() != capacity {
+                resizes += 1;
+                rehashed += hash_table.size() - 1;
+            }
+        }
 
-        assert_eq!(hash_table.get("key_1".to_string()), Some(1));
-        assert_eq!(hash_table.get("key_22".to_string()), Some(2));
-        assert_eq!(hash_table.get("key_33".to_string()), Some(3));
-        assert_eq!(hash_table.get("key_4".to_string()), Some(4));
+        // Geometric growth: logarithmically many resizes, moving O(n) entries in total
+        assert!(resizes <= 25, "{} resizes", resizes);
+        assert!(rehashed < 2 * n as usize, "{} entries rehashed", rehashed);
+        assert_eq!(hash_table.get(&(n - 1)), Some(&(n - 1)));
     }
 }
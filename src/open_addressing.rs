@@ -0,0 +1,360 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+
+use crate::KV;
+
+// Keep at least a quarter of the slots empty, so every probe sequence terminates quickly
+const MAX_LOAD_FACTOR: f64 = 0.75;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Probe {
+    Linear,
+    Quadratic,
+    DoubleHashing,
+}
+
+#[derive(Clone, Debug)]
+enum Slot<K, V> {
+    Empty,
+    // A deleted entry. Lookups keep probing past it, inserts may reuse it.
+    Tombstone,
+    Occupied(KV<K, V>),
+}
+
+/// Open addressing table: every slot holds at most one entry, collisions are
+/// resolved by walking the probe sequence until an empty slot is found.
+#[derive(Clone, Debug)]
+pub struct OpenHashTable<K, V, S = RandomState> {
+    slots: Vec<Slot<K, V>>,
+    size: usize,
+    tombstones: usize,
+    probe: Probe,
+    hash_builder: S,
+}
+
+impl<K, V> OpenHashTable<K, V, RandomState>
+where
+    K: Hash + Eq,
+{
+    pub fn new(with_capacity: usize) -> Self {
+        Self::with_probe(with_capacity, Probe::Linear)
+    }
+
+    pub fn with_probe(with_capacity: usize, probe: Probe) -> Self {
+        Self::with_probe_and_hasher(with_capacity, probe, RandomState::new())
+    }
+}
+
+impl<K, V, S> OpenHashTable<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn with_probe_and_hasher(with_capacity: usize, probe: Probe, hash_builder: S) -> Self {
+        // Power of two length: quadratic (triangular) and double hashing probes
+        // are only guaranteed to visit every slot under this condition
+        let len = with_capacity.max(1).next_power_of_two();
+
+        OpenHashTable {
+            slots: Self::empty_slots(len),
+            size: 0,
+            tombstones: 0,
+            probe,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn probe(&self) -> Probe {
+        self.probe
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if (self.size + self.tombstones + 1) as f64 > self.slots.len() as f64 * MAX_LOAD_FACTOR {
+            self.resize();
+        }
+
+        let hash = self.hash_builder.hash_one(&key);
+        let mut free = None;
+
+        for index in self.probe_sequence(hash) {
+            match &mut self.slots[index] {
+                Slot::Occupied(kv) if kv.key == key => {
+                    return Some(mem::replace(&mut kv.value, value));
+                }
+                Slot::Occupied(_) => {}
+                Slot::Tombstone => {
+                    free.get_or_insert(index);
+                }
+                Slot::Empty => {
+                    free.get_or_insert(index);
+                    break;
+                }
+            }
+        }
+
+        let index = free.expect("load factor keeps at least one free slot");
+        if let Slot::Tombstone = self.slots[index] {
+            self.tombstones -= 1;
+        }
+
+        self.slots[index] = Slot::Occupied(KV::new(key, value));
+        self.size += 1;
+
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).map(|index| match &self.slots[index] {
+            Slot::Occupied(kv) => &kv.value,
+            _ => unreachable!(),
+        })
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).map(|index| match &mut self.slots[index] {
+            Slot::Occupied(kv) => &mut kv.value,
+            _ => unreachable!(),
+        })
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    pub fn delete<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(key)?;
+
+        let removed = match mem::replace(&mut self.slots[index], Slot::Tombstone) {
+            Slot::Occupied(kv) => kv.value,
+            _ => unreachable!(),
+        };
+
+        self.size -= 1;
+        self.tombstones += 1;
+
+        // Tombstones make misses walk longer chains; once they take up as much
+        // room as live entries it's cheaper to rehash than to keep probing past them
+        if self.tombstones > self.size {
+            self.rehash(self.slots.len());
+        }
+
+        Some(removed)
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_builder.hash_one(key);
+
+        for index in self.probe_sequence(hash) {
+            match &self.slots[index] {
+                Slot::Occupied(kv) if kv.key.borrow() == key => return Some(index),
+                Slot::Empty => return None,
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    fn probe_sequence(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let mask = self.slots.len() - 1;
+        let start = hash as usize;
+        // Second hash for double hashing comes from the high bits; forcing it odd
+        // makes it coprime with the power of two length
+        let step = ((hash >> 32) as usize) | 1;
+        let probe = self.probe;
+
+        (0..self.slots.len()).map(move |i| {
+            let offset = match probe {
+                Probe::Linear => i,
+                Probe::Quadratic => i * (i + 1) / 2,
+                Probe::DoubleHashing => i.wrapping_mul(step),
+            };
+
+            start.wrapping_add(offset) & mask
+        })
+    }
+
+    fn resize(&mut self) {
+        // Only grow if live entries need the room, otherwise dropping the tombstones is enough
+        let len = if (self.size + 1) as f64 > self.slots.len() as f64 * MAX_LOAD_FACTOR / 2.0 {
+            self.slots.len() * 2
+        } else {
+            self.slots.len()
+        };
+
+        self.rehash(len);
+    }
+
+    fn rehash(&mut self, len: usize) {
+        let old_slots = mem::replace(&mut self.slots, Self::empty_slots(len));
+        self.tombstones = 0;
+
+        for slot in old_slots {
+            if let Slot::Occupied(kv) = slot {
+                let index = self
+                    .probe_sequence(self.hash_builder.hash_one(&kv.key))
+                    .find(|&index| matches!(self.slots[index], Slot::Empty))
+                    .expect("rehashed table has free slots");
+
+                self.slots[index] = Slot::Occupied(kv);
+            }
+        }
+    }
+
+    fn empty_slots(len: usize) -> Vec<Slot<K, V>> {
+        (0..len).map(|_| Slot::Empty).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashers::FxBuildHasher;
+
+    const PROBES: [Probe; 3] = [Probe::Linear, Probe::Quadratic, Probe::DoubleHashing];
+
+    #[test]
+    fn test_shared_start_slot() {
+        // Under FxHash these keys all start probing at slot 0 (see `FxHasher`),
+        // so only the probing spreads them out
+        for probe in PROBES {
+            let mut hash_table =
+                OpenHashTable::with_probe_and_hasher(4, probe, FxBuildHasher::default());
+
+            for i in 0..100u64 {
+                assert_eq!(hash_table.insert(i << 32, i), None);
+            }
+
+            assert_eq!(hash_table.len(), 100);
+            assert_eq!(hash_table.insert(7 << 32, 700), Some(7));
+            *hash_table.get_mut(&(8 << 32)).unwrap() = 800;
+
+            for i in 0..100u64 {
+                let expected = match i {
+                    7 => 700,
+                    8 => 800,
+                    _ => i,
+                };
+                assert_eq!(hash_table.get(&(i << 32)), Some(&expected));
+            }
+
+            assert!(!hash_table.contains_key(&(100u64 << 32)));
+        }
+    }
+
+    #[test]
+    fn test_borrowed_lookup_past_tombstone() {
+        let mut hash_table =
+            OpenHashTable::with_probe_and_hasher(64, Probe::Linear, FxBuildHasher::default());
+
+        for i in 0..40 {
+            hash_table.insert(format!("key_{i}"), i);
+        }
+        for i in (0..40).step_by(4) {
+            assert_eq!(hash_table.delete(format!("key_{i}").as_str()), Some(i));
+        }
+
+        // Keys whose probe sequence walks over a tombstone before reaching them
+        let past_tombstone: Vec<_> = (0..40)
+            .filter(|i| i % 4 != 0)
+            .map(|i| format!("key_{i}"))
+            .filter(|key| {
+                let hash = hash_table.hash_builder.hash_one(key.as_str());
+                hash_table
+                    .probe_sequence(hash)
+                    .take_while(|&index| {
+                        !matches!(&hash_table.slots[index], Slot::Occupied(kv) if kv.key == *key)
+                    })
+                    .any(|index| matches!(hash_table.slots[index], Slot::Tombstone))
+            })
+            .collect();
+
+        assert!(!past_tombstone.is_empty());
+        for key in &past_tombstone {
+            assert!(hash_table.get(key.as_str()).is_some());
+            assert!(hash_table.contains_key(key.as_str()));
+        }
+        assert!(!hash_table.contains_key("key_0"));
+    }
+
+    #[test]
+    fn test_delete_keeps_probe_chains() {
+        for probe in PROBES {
+            let mut hash_table = OpenHashTable::with_probe(64, probe);
+
+            for i in 0..40 {
+                hash_table.insert(i, i * 10);
+            }
+
+            for i in (0..40).step_by(2) {
+                assert_eq!(hash_table.delete(&i), Some(i * 10));
+            }
+
+            assert_eq!(hash_table.size(), 20);
+            assert_eq!(hash_table.delete(&0), None);
+
+            for i in 0..40 {
+                let expected = if i % 2 == 0 { None } else { Some(i * 10) };
+                assert_eq!(hash_table.get(&i).copied(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tombstone_cleanup() {
+        let mut hash_table = OpenHashTable::new(16);
+
+        for i in 0..10 {
+            hash_table.insert(i, i);
+        }
+
+        for i in 0..6 {
+            hash_table.delete(&i);
+        }
+
+        assert!(hash_table.tombstones <= hash_table.size());
+        assert_eq!(hash_table.slots.len(), 16);
+
+        for i in 6..10 {
+            assert_eq!(hash_table.get(&i), Some(&i));
+        }
+    }
+}
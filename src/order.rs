@@ -0,0 +1,39 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+/// How a `HashTable` tells apart keys that share their full hash, once their
+/// bucket got a tree bin.
+///
+/// `()` doesn't order keys: a lookup calls `Eq` on every key stored under its hash.
+/// `ByKey` sorts them with `Ord` and binary searches them, so even keys an attacker
+/// made collide on all 64 hash bits cost O(log n) comparisons to look up.
+pub trait KeyOrder<K: ?Sized, Q: ?Sized = K> {
+    // Whether tree bins sort keys with `compare` at all
+    const ORDERED: bool;
+
+    fn compare(key: &Q, other: &K) -> Ordering;
+}
+
+impl<K: ?Sized, Q: ?Sized> KeyOrder<K, Q> for () {
+    const ORDERED: bool = false;
+
+    fn compare(_key: &Q, _other: &K) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+/// Orders keys sharing a full hash by their `Ord` impl, see `KeyOrder`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ByKey;
+
+impl<K, Q> KeyOrder<K, Q> for ByKey
+where
+    K: Borrow<Q> + ?Sized,
+    Q: Ord + ?Sized,
+{
+    const ORDERED: bool = true;
+
+    fn compare(key: &Q, other: &K) -> Ordering {
+        key.cmp(other.borrow())
+    }
+}
@@ -0,0 +1,161 @@
+/// When and how much a `HashTable` grows.
+///
+/// The table resizes once an insert would push `size / buckets` over
+/// `max_load_factor`, multiplying the bucket count by `growth_factor`. Growing
+/// geometrically keeps inserts amortized O(1).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GrowthPolicy {
+    pub max_load_factor: f64,
+    pub growth_factor: f64,
+    // Round bucket counts up to the next power of two
+    pub power_of_two: bool,
+    pub rehash: RehashMode,
+    // Low-water mark: a delete that drops the load below it shrinks the bucket array
+    pub min_load_factor: Option<f64>,
+    pub index: IndexMode,
+}
+
+/// How entries get moved into the new bucket array after a resize.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RehashMode {
+    // Everything is moved inside the insert that triggered the resize
+    Blocking,
+    // Both arrays are kept and every write moves `buckets_per_step` old buckets,
+    // so no single operation pays for the whole table. The next resize waits for
+    // the old array to drain, letting the load run over `max_load_factor` meanwhile.
+    Incremental { buckets_per_step: usize },
+}
+
+/// How a hash is mapped onto a bucket index.
+///
+/// Every mode spreads uniformly distributed hashes evenly over the buckets; they
+/// differ in cost and in which hash bits they read. `Modulo` divides on every
+/// lookup, the other two only multiply. That only pays off once the table fits in
+/// cache: on big tables the cache misses of the chain walk hide it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IndexMode {
+    // `hash % len`: reads every bit, works for any bucket count
+    #[default]
+    Modulo,
+    // Fibonacci hashing: multiplies by 2^64 / golden ratio and keeps the top bits.
+    // Needs `power_of_two`, and mixes weak hashes that only differ in their low bits.
+    MultiplyShift,
+    // Lemire's fastrange: `hash * len / 2^64`, any bucket count. Reads mostly the
+    // high bits, so it wants a hasher that mixes them (`RandomState` does).
+    FastRange,
+}
+
+// 2^64 / golden ratio, rounded to odd
+const FIBONACCI_MULTIPLIER: u64 = 0x9e37_79b9_7f4a_7c15;
+
+impl IndexMode {
+    pub(crate) fn bucket_index(self, hash: u64, len: usize) -> usize {
+        match self {
+            // Modulo arithmetic -> Uniform Distribution
+            IndexMode::Modulo => (hash % (len as u64)) as usize,
+            // The multiply is a bijection, so uniform hashes stay uniform, and its top
+            // `log2(len)` bits depend on every bit of `hash`. A single bucket keeps none.
+            IndexMode::MultiplyShift => hash
+                .wrapping_mul(FIBONACCI_MULTIPLIER)
+                .checked_shr(64 - len.trailing_zeros())
+                .unwrap_or(0) as usize,
+            // Scales 0..2^64 down to 0..len: every bucket gets floor or ceil of 2^64 / len hashes
+            IndexMode::FastRange => ((hash as u128 * len as u128) >> 64) as usize,
+        }
+    }
+}
+
+impl GrowthPolicy {
+    pub(crate) fn validate(&self) {
+        assert!(
+            self.max_load_factor > 0.0,
+            "max load factor must be positive"
+        );
+        assert!(
+            self.growth_factor > 1.0,
+            "growth factor must be greater than 1"
+        );
+
+        // Right after a shrink the load is max_load_factor / growth_factor; a low-water
+        // mark above that would shrink again straight away
+        if let Some(min_load_factor) = self.min_load_factor {
+            assert!(
+                min_load_factor < self.max_load_factor / self.growth_factor,
+                "min load factor must be below max load factor / growth factor"
+            );
+        }
+
+        if self.index == IndexMode::MultiplyShift {
+            assert!(
+                self.power_of_two,
+                "multiply-shift indexing needs power of two bucket counts"
+            );
+        }
+
+        if let RehashMode::Incremental { buckets_per_step } = self.rehash {
+            assert!(
+                buckets_per_step > 0,
+                "rehash step must move at least one bucket"
+            );
+        }
+    }
+
+    pub(crate) fn exceeds_load(&self, size: usize, buckets: usize) -> bool {
+        size as f64 > buckets as f64 * self.max_load_factor
+    }
+
+    // Smallest bucket count reached by growing from `buckets` that fits `size` entries.
+    // Saturates at `usize::MAX`, which no allocation can satisfy.
+    pub(crate) fn grown_len(&self, buckets: usize, size: usize) -> usize {
+        let mut len = buckets;
+
+        loop {
+            len = ((len as f64 * self.growth_factor).ceil() as usize).max(len.saturating_add(1));
+
+            if self.power_of_two {
+                len = len.checked_next_power_of_two().unwrap_or(usize::MAX);
+            }
+
+            if len == usize::MAX || !self.exceeds_load(size, len) {
+                return len;
+            }
+        }
+    }
+
+    // Fewest buckets that hold `size` entries without going over the max load factor.
+    // No entries need no buckets at all.
+    pub(crate) fn min_len(&self, size: usize) -> usize {
+        let len = (size as f64 / self.max_load_factor).ceil() as usize;
+
+        if self.power_of_two && len > 0 {
+            len.next_power_of_two()
+        } else {
+            len
+        }
+    }
+
+    // Bucket count to shrink to once `size` fell under the low-water mark of a `buckets` long array
+    pub(crate) fn shrunk_len(&self, buckets: usize, size: usize) -> Option<usize> {
+        let min_load_factor = self.min_load_factor?;
+
+        if size as f64 >= buckets as f64 * min_load_factor {
+            return None;
+        }
+
+        let len = self.min_len((size as f64 * self.growth_factor).ceil() as usize);
+        (len < buckets).then_some(len)
+    }
+}
+
+impl Default for GrowthPolicy {
+    fn default() -> Self {
+        GrowthPolicy {
+            max_load_factor: 0.75,
+            growth_factor: 2.0,
+            power_of_two: false,
+            rehash: RehashMode::Blocking,
+            min_load_factor: None,
+            index: IndexMode::Modulo,
+        }
+    }
+}
@@ -0,0 +1,973 @@
+use std::alloc::Layout;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::iter::{Chain, Flatten, FusedIterator};
+use std::marker::PhantomData;
+use std::{mem, slice, vec};
+
+use crate::tree_bin::{Order, TreeBin, TREEIFY_THRESHOLD, UNTREEIFY_THRESHOLD};
+use crate::{GrowthPolicy, RehashMode, TryReserveError};
+
+// Separate chaining: every value hashing to the same index lives in the same chain
+pub(crate) type Bucket<T> = Vec<T>;
+type Buckets<T> = Vec<Bucket<T>>;
+// Tree bins of the overly long chains of one bucket array, by bucket index
+type TreeBins = BTreeMap<usize, TreeBin>;
+
+// Old bucket array of an incremental resize, emptied bucket by bucket into the new one
+#[derive(Clone, Debug)]
+pub(crate) struct Rehash<T> {
+    pub(crate) buckets: Buckets<T>,
+    pub(crate) tree_bins: TreeBins,
+    // Every bucket before this one has been moved
+    pub(crate) next: usize,
+}
+
+// What a lookup is after: the first value `eq` accepts. An `ORDERED` search also
+// tells which way its value sorts from others, the same way the table sorts its tree
+// bins, so values sharing the full hash are binary searched instead of tried in turn.
+pub(crate) trait Search<T> {
+    const ORDERED: bool = false;
+
+    fn eq(&mut self, value: &T) -> bool;
+
+    fn cmp(&mut self, _value: &T) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+impl<T, F: FnMut(&T) -> bool> Search<T> for F {
+    fn eq(&mut self, value: &T) -> bool {
+        self(value)
+    }
+}
+
+/// The bucket arrays under `HashTable`, addressed by precomputed hashes.
+///
+/// Lookups take an equality closure instead of a key, and anything that may move
+/// values into a resized bucket array takes a `hasher` closure that gives the hash
+/// of a stored value. Callers pick both the hash function and what counts as equal,
+/// as long as `eq` only accepts values stored under the hash it's passed with.
+///
+/// A chain that grows past a handful of values gets a tree bin, which keeps lookups
+/// in it O(log n) however many keys an attacker makes collide on the bucket index.
+#[derive(Clone, Debug)]
+pub struct RawTable<T> {
+    pub(crate) buckets: Buckets<T>,
+    pub(crate) tree_bins: TreeBins,
+    pub(crate) size: usize,
+    pub(crate) policy: GrowthPolicy,
+    pub(crate) rehashing: Option<Rehash<T>>,
+    // Sorts tree bin values sharing a full hash; only `HashTable` sets one
+    pub(crate) order: Order<T>,
+}
+
+// Mid-resize values are spread over the old and the new bucket array, so every
+// iterator walks both: old buckets first, then new ones
+type ChainedBuckets<'a, T> = Chain<slice::Iter<'a, Bucket<T>>, slice::Iter<'a, Bucket<T>>>;
+type ChainedBucketsMut<'a, T> = Chain<slice::IterMut<'a, Bucket<T>>, slice::IterMut<'a, Bucket<T>>>;
+type IntoBuckets<T> = Chain<vec::IntoIter<Bucket<T>>, vec::IntoIter<Bucket<T>>>;
+
+pub struct RawIter<'a, T> {
+    inner: Flatten<ChainedBuckets<'a, T>>,
+    remaining: usize,
+}
+
+pub struct RawIterMut<'a, T> {
+    inner: Flatten<ChainedBucketsMut<'a, T>>,
+    remaining: usize,
+}
+
+pub struct RawIntoIter<T> {
+    inner: Flatten<IntoBuckets<T>>,
+    remaining: usize,
+}
+
+// Owns the drained buckets; the table is already empty while this is alive
+pub struct RawDrain<'a, T> {
+    inner: RawIntoIter<T>,
+    marker: PhantomData<&'a mut Bucket<T>>,
+}
+
+// Every value sharing a bucket with some hash, new array first
+pub struct RawIterHash<'a, T> {
+    inner: Chain<slice::Iter<'a, T>, slice::Iter<'a, T>>,
+}
+
+impl<T> RawTable<T> {
+    pub fn new() -> Self {
+        Self::with_buckets(0)
+    }
+
+    pub fn with_buckets(len: usize) -> Self {
+        RawTable {
+            buckets: empty_buckets(len),
+            tree_bins: TreeBins::new(),
+            size: 0,
+            policy: GrowthPolicy::default(),
+            rehashing: None,
+            order: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn num_buckets(&self) -> usize {
+        self.buckets.len()
+    }
+
+    // Number of values the table holds before it has to grow
+    pub fn capacity(&self) -> usize {
+        (self.buckets.len() as f64 * self.policy.max_load_factor) as usize
+    }
+
+    pub fn growth_policy(&self) -> &GrowthPolicy {
+        &self.policy
+    }
+
+    // Switching the index mode moves every value to another bucket, so it's only
+    // allowed while the table is empty; `HashTable` rebuilds itself instead
+    pub fn set_growth_policy(&mut self, policy: GrowthPolicy) {
+        policy.validate();
+
+        if policy.index != self.policy.index {
+            assert!(
+                self.is_empty(),
+                "index mode can only change on an empty table"
+            );
+
+            let len = match policy.power_of_two && !self.buckets.is_empty() {
+                true => self.buckets.len().next_power_of_two(),
+                false => self.buckets.len(),
+            };
+            self.buckets = empty_buckets(len);
+            self.tree_bins.clear();
+            self.rehashing = None;
+        }
+
+        self.policy = policy;
+    }
+
+    // An incremental resize still has old buckets to move
+    pub fn is_rehashing(&self) -> bool {
+        self.rehashing.is_some()
+    }
+
+    // First value in `hash`'s bucket that `eq` accepts
+    pub fn find(&self, hash: u64, mut eq: impl FnMut(&T) -> bool) -> Option<&T> {
+        self.find_by(hash, &mut eq)
+    }
+
+    pub fn find_mut(&mut self, hash: u64, mut eq: impl FnMut(&T) -> bool) -> Option<&mut T> {
+        self.find_mut_by(hash, &mut eq)
+    }
+
+    // Adds `value` without looking for an equal one first
+    pub fn insert(&mut self, hash: u64, value: T, hasher: impl Fn(&T) -> u64) -> &mut T {
+        self.try_insert(hash, value, hasher)
+            .unwrap_or_else(|err| err.handle())
+    }
+
+    // Like `insert`, but reports a failed allocation instead of aborting
+    pub fn try_insert(
+        &mut self,
+        hash: u64,
+        value: T,
+        hasher: impl Fn(&T) -> u64,
+    ) -> Result<&mut T, TryReserveError> {
+        self.write_step(&hasher);
+
+        let (index, position) = self.insert_unique(hash, value, &hasher)?;
+        Ok(&mut self.buckets[index][position])
+    }
+
+    // Takes out the first value in `hash`'s bucket that `eq` accepts. Never resizes,
+    // `shrink_to` gives the memory back.
+    pub fn remove_entry(&mut self, hash: u64, mut eq: impl FnMut(&T) -> bool) -> Option<T> {
+        self.remove_entry_by(hash, &mut eq)
+    }
+
+    // Makes room for `additional` more values without growing on the way
+    pub fn reserve(&mut self, additional: usize, hasher: impl Fn(&T) -> u64) {
+        self.try_reserve(additional, hasher)
+            .unwrap_or_else(|err| err.handle())
+    }
+
+    // Like `reserve`, but reports a failed allocation instead of aborting
+    pub fn try_reserve(
+        &mut self,
+        additional: usize,
+        hasher: impl Fn(&T) -> u64,
+    ) -> Result<(), TryReserveError> {
+        let size = self
+            .size
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+
+        if self.policy.exceeds_load(size, self.buckets.len()) {
+            self.try_resize_to(self.policy.grown_len(self.buckets.len(), size), &hasher)?;
+        }
+
+        Ok(())
+    }
+
+    // Shrinks the bucket array as far as the values and `min_capacity` allow
+    pub fn shrink_to(&mut self, min_capacity: usize, hasher: impl Fn(&T) -> u64) {
+        let new_len = self.policy.min_len(self.size.max(min_capacity));
+
+        if new_len < self.buckets.len() {
+            self.resize_to(new_len, &hasher);
+        }
+    }
+
+    // Keeps only the values `f` returns true for, in a single pass over the buckets.
+    // Never resizes.
+    pub fn retain(&mut self, mut f: impl FnMut(&mut T) -> bool) {
+        if let Some(rehash) = &mut self.rehashing {
+            retain_chains(
+                &mut rehash.buckets,
+                &mut rehash.tree_bins,
+                &mut self.size,
+                &mut f,
+                self.order,
+            );
+        }
+
+        retain_chains(
+            &mut self.buckets,
+            &mut self.tree_bins,
+            &mut self.size,
+            &mut f,
+            self.order,
+        );
+    }
+
+    pub fn iter(&self) -> RawIter<'_, T> {
+        let old: &[Bucket<T>] = match &self.rehashing {
+            Some(rehash) => &rehash.buckets,
+            None => &[],
+        };
+
+        RawIter {
+            inner: old.iter().chain(self.buckets.iter()).flatten(),
+            remaining: self.size,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> RawIterMut<'_, T> {
+        let old: &mut [Bucket<T>] = match &mut self.rehashing {
+            Some(rehash) => &mut rehash.buckets,
+            None => &mut [],
+        };
+
+        RawIterMut {
+            inner: old.iter_mut().chain(self.buckets.iter_mut()).flatten(),
+            remaining: self.size,
+        }
+    }
+
+    // Values sharing `hash`'s bucket, whether or not their own hash matches it
+    pub fn iter_hash(&self, hash: u64) -> RawIterHash<'_, T> {
+        let new: &[T] = match self.buckets.is_empty() {
+            true => &[],
+            false => &self.buckets[self.create_index(hash)],
+        };
+        let old: &[T] = match &self.rehashing {
+            Some(rehash) => {
+                &rehash.buckets[self.policy.index.bucket_index(hash, rehash.buckets.len())]
+            }
+            None => &[],
+        };
+
+        RawIterHash {
+            inner: new.iter().chain(old),
+        }
+    }
+
+    // Empties the table up front, keeping the bucket array length
+    pub fn drain(&mut self) -> RawDrain<'_, T> {
+        let len = self.buckets.len();
+        let buckets = mem::replace(&mut self.buckets, empty_buckets(len));
+        self.tree_bins.clear();
+        let old = self
+            .rehashing
+            .take()
+            .map(|rehash| rehash.buckets)
+            .unwrap_or_default();
+
+        RawDrain {
+            inner: RawIntoIter {
+                inner: old.into_iter().chain(buckets).flatten(),
+                remaining: mem::take(&mut self.size),
+            },
+            marker: PhantomData,
+        }
+    }
+
+    // The lookups above, for searches other than `eq` closures
+    pub(crate) fn find_by(&self, hash: u64, search: &mut impl Search<T>) -> Option<&T> {
+        if let Some((index, position)) = self.locate(hash, search) {
+            return Some(&self.buckets[index][position]);
+        }
+
+        // Mid-resize the value may still sit in the old array
+        let rehash = self.rehashing.as_ref()?;
+        let index = self.policy.index.bucket_index(hash, rehash.buckets.len());
+        let chain = &rehash.buckets[index];
+        let position = chain_position(chain, rehash.tree_bins.get(&index), hash, search)?;
+
+        Some(&chain[position])
+    }
+
+    pub(crate) fn find_mut_by(&mut self, hash: u64, search: &mut impl Search<T>) -> Option<&mut T> {
+        if let Some((index, position)) = self.locate(hash, search) {
+            return Some(&mut self.buckets[index][position]);
+        }
+
+        // Mid-resize the value may still sit in the old array
+        let rehash = self.rehashing.as_mut()?;
+        let index = self.policy.index.bucket_index(hash, rehash.buckets.len());
+        let chain = &mut rehash.buckets[index];
+        let position = chain_position(chain, rehash.tree_bins.get(&index), hash, search)?;
+
+        Some(&mut chain[position])
+    }
+
+    pub(crate) fn remove_entry_by(&mut self, hash: u64, search: &mut impl Search<T>) -> Option<T> {
+        if let Some((index, position)) = self.locate(hash, search) {
+            return Some(self.remove_at(index, position));
+        }
+
+        let rehash = self.rehashing.as_mut()?;
+        let index = self.policy.index.bucket_index(hash, rehash.buckets.len());
+        let chain = &mut rehash.buckets[index];
+        let position = chain_position(chain, rehash.tree_bins.get(&index), hash, search)?;
+
+        self.size -= 1;
+        Some(chain_swap_remove(
+            chain,
+            &mut rehash.tree_bins,
+            index,
+            position,
+            self.order,
+        ))
+    }
+
+    pub(crate) fn create_index(&self, hash: u64) -> usize {
+        self.policy.index.bucket_index(hash, self.buckets.len())
+    }
+
+    // Bucket and chain position of a value in the new array. Writes that keep the
+    // position around migrate the hash's old bucket first.
+    pub(crate) fn locate(&self, hash: u64, search: &mut impl Search<T>) -> Option<(usize, usize)> {
+        if self.buckets.is_empty() {
+            return None;
+        }
+
+        let index = self.create_index(hash);
+        let position = chain_position(
+            &self.buckets[index],
+            self.tree_bins.get(&index),
+            hash,
+            search,
+        )?;
+
+        Some((index, position))
+    }
+
+    // Adds a value known to be absent, returns where it landed
+    pub(crate) fn insert_unique(
+        &mut self,
+        hash: u64,
+        value: T,
+        hasher: &impl Fn(&T) -> u64,
+    ) -> Result<(usize, usize), TryReserveError> {
+        // Like Redis, the load may run over the limit until an incremental resize has
+        // landed: starting the next one now would move the rest of the old array
+        // inside this single insert
+        if self.policy.exceeds_load(self.size + 1, self.buckets.len()) && !self.mid_resize() {
+            self.try_resize_to(
+                self.policy.grown_len(self.buckets.len(), self.size + 1),
+                hasher,
+            )?;
+        }
+
+        let index = self.create_index(hash);
+        let chain = &mut self.buckets[index];
+        try_reserve_chain(chain)?;
+        let position = chain_push(
+            chain,
+            &mut self.tree_bins,
+            index,
+            hash,
+            value,
+            hasher,
+            self.order,
+        );
+        self.size += 1;
+
+        Ok((index, position))
+    }
+
+    pub(crate) fn remove_at(&mut self, index: usize, position: usize) -> T {
+        let value = chain_swap_remove(
+            &mut self.buckets[index],
+            &mut self.tree_bins,
+            index,
+            position,
+            self.order,
+        );
+        self.size -= 1;
+
+        value
+    }
+
+    // Low-water mark of the growth policy. Waits for an incremental resize to land,
+    // the same way growing does.
+    pub(crate) fn shrink_if_sparse(&mut self, hasher: &impl Fn(&T) -> u64) {
+        if self.mid_resize() {
+            return;
+        }
+
+        if let Some(new_len) = self.policy.shrunk_len(self.buckets.len(), self.size) {
+            self.resize_to(new_len, hasher);
+        }
+    }
+
+    // Removes and returns the next value from the cursor on that `pred` returns true
+    // for. The cursor runs over the old array's buckets followed by the new array's.
+    pub(crate) fn extract_next(
+        &mut self,
+        bucket: &mut usize,
+        position: &mut usize,
+        mut pred: impl FnMut(&mut T) -> bool,
+    ) -> Option<T> {
+        let mut no_tree_bins = TreeBins::new();
+        let (old, old_tree_bins) = match &mut self.rehashing {
+            Some(rehash) => (&mut rehash.buckets[..], &mut rehash.tree_bins),
+            None => (&mut [][..], &mut no_tree_bins),
+        };
+        let old_len = old.len();
+
+        loop {
+            let (chain, tree_bins, index) = match bucket.checked_sub(old_len) {
+                None => (&mut old[*bucket], &mut *old_tree_bins, *bucket),
+                Some(index) => (self.buckets.get_mut(index)?, &mut self.tree_bins, index),
+            };
+
+            let Some(value) = chain.get_mut(*position) else {
+                *bucket += 1;
+                *position = 0;
+                continue;
+            };
+
+            if pred(value) {
+                // swap_remove pulls the chain's last value into this position, so the
+                // cursor stays put. Size is updated before handing the value out.
+                let value = chain_swap_remove(chain, tree_bins, index, *position, self.order);
+                self.size -= 1;
+                return Some(value);
+            }
+
+            *position += 1;
+        }
+    }
+
+    // SAFETY: the caller makes sure the slots are in bounds and no two are the same.
+    // The values are reached through raw pointers so no reference to a whole chain
+    // invalidates the others.
+    pub(crate) unsafe fn slots_mut<const N: usize>(
+        &mut self,
+        slots: [(usize, usize); N],
+    ) -> [&mut T; N] {
+        let buckets = self.buckets.as_mut_ptr();
+
+        slots.map(|(index, position)| unsafe {
+            let chain = (*buckets.add(index)).as_mut_ptr();
+            &mut *chain.add(position)
+        })
+    }
+
+    fn resize_to(&mut self, new_len: usize, hasher: &impl Fn(&T) -> u64) {
+        self.try_resize_to(new_len, hasher)
+            .unwrap_or_else(|err| err.handle())
+    }
+
+    fn try_resize_to(
+        &mut self,
+        new_len: usize,
+        hasher: &impl Fn(&T) -> u64,
+    ) -> Result<(), TryReserveError> {
+        // Allocated before anything moves, so a failure leaves the table untouched
+        let new_buckets = try_empty_buckets(new_len)?;
+
+        // A resize still in flight has to land before the next one starts. Only
+        // `reserve` and `shrink_to` get here mid-resize, writes wait for it instead.
+        self.rehash_step(usize::MAX, hasher);
+
+        let old_buckets = mem::replace(&mut self.buckets, new_buckets);
+        let old_tree_bins = mem::take(&mut self.tree_bins);
+
+        if !old_buckets.is_empty() {
+            self.rehashing = Some(Rehash {
+                buckets: old_buckets,
+                tree_bins: old_tree_bins,
+                next: 0,
+            });
+        }
+
+        if self.policy.rehash == RehashMode::Blocking {
+            self.rehash_step(usize::MAX, hasher);
+        }
+
+        Ok(())
+    }
+
+    // Old buckets left to move, with a new array to move them into. A table shrunk
+    // to no buckets at all only has empty ones left, and grows straight away.
+    fn mid_resize(&self) -> bool {
+        self.rehashing.is_some() && !self.buckets.is_empty()
+    }
+
+    // Every write pays for a bounded slice of an incremental resize
+    pub(crate) fn write_step(&mut self, hasher: &impl Fn(&T) -> u64) {
+        match self.policy.rehash {
+            RehashMode::Incremental { buckets_per_step } => {
+                let steps = buckets_per_step.max(self.catch_up_steps());
+                self.rehash_step(steps, hasher)
+            }
+            RehashMode::Blocking => self.rehash_step(usize::MAX, hasher),
+        }
+    }
+
+    // Old buckets every write has to move for a growing resize to land before the
+    // inserts left until the next one run out. That's about 1 / (max_load_factor *
+    // (growth_factor - 1)) whatever the table size, and only falls as it goes.
+    // Shrinking, or with no room left, the next resize waits instead.
+    fn catch_up_steps(&self) -> usize {
+        let Some(rehash) = &self.rehashing else {
+            return 0;
+        };
+        if rehash.buckets.len() >= self.buckets.len() {
+            return 0;
+        }
+
+        let left = rehash.buckets.len() - rehash.next;
+        let room = self.capacity().saturating_sub(self.size);
+
+        match room {
+            0 => 0,
+            room => left.div_ceil(room),
+        }
+    }
+
+    // Moves up to `steps` old buckets, dropping the old array once it's drained
+    fn rehash_step(&mut self, steps: usize, hasher: &impl Fn(&T) -> u64) {
+        for _ in 0..steps {
+            let Some(rehash) = &mut self.rehashing else {
+                return;
+            };
+
+            let index = rehash.next;
+            rehash.next += 1;
+            let done = rehash.next == rehash.buckets.len();
+
+            self.migrate_bucket(index, hasher);
+
+            if done {
+                self.rehashing = None;
+            }
+        }
+    }
+
+    // Positions handed out by `locate` point into the new array, so the hash's old
+    // bucket is moved first, out of turn. The sequential pass later finds it empty.
+    pub(crate) fn migrate_bucket_of(&mut self, hash: u64, hasher: &impl Fn(&T) -> u64) {
+        if let Some(rehash) = &self.rehashing {
+            self.migrate_bucket(
+                self.policy.index.bucket_index(hash, rehash.buckets.len()),
+                hasher,
+            );
+        }
+    }
+
+    fn migrate_bucket(&mut self, index: usize, hasher: &impl Fn(&T) -> u64) {
+        let Some(rehash) = &mut self.rehashing else {
+            return;
+        };
+
+        rehash.tree_bins.remove(&index);
+
+        for value in mem::take(&mut rehash.buckets[index]) {
+            let hash = hasher(&value);
+            let index = self.create_index(hash);
+            chain_push(
+                &mut self.buckets[index],
+                &mut self.tree_bins,
+                index,
+                hash,
+                value,
+                hasher,
+                self.order,
+            );
+        }
+    }
+}
+
+impl<T> Default for RawTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> IntoIterator for RawTable<T> {
+    type Item = T;
+    type IntoIter = RawIntoIter<T>;
+
+    fn into_iter(self) -> RawIntoIter<T> {
+        let old = self
+            .rehashing
+            .map(|rehash| rehash.buckets)
+            .unwrap_or_default();
+
+        RawIntoIter {
+            inner: old.into_iter().chain(self.buckets).flatten(),
+            remaining: self.size,
+        }
+    }
+}
+
+impl<'a, T> Iterator for RawIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let value = self.inner.next()?;
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> Iterator for RawIterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        let value = self.inner.next()?;
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> Iterator for RawIntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let value = self.inner.next()?;
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> Iterator for RawDrain<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T> Iterator for RawIterHash<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for RawIter<'_, T> {}
+impl<T> ExactSizeIterator for RawIterMut<'_, T> {}
+impl<T> ExactSizeIterator for RawIntoIter<T> {}
+impl<T> ExactSizeIterator for RawDrain<'_, T> {}
+
+// Flatten and Chain over slice / vec iterators keep returning None once done
+impl<T> FusedIterator for RawIter<'_, T> {}
+impl<T> FusedIterator for RawIterMut<'_, T> {}
+impl<T> FusedIterator for RawIntoIter<T> {}
+impl<T> FusedIterator for RawDrain<'_, T> {}
+impl<T> FusedIterator for RawIterHash<'_, T> {}
+
+impl<T> Clone for RawIter<'_, T> {
+    fn clone(&self) -> Self {
+        RawIter {
+            inner: self.inner.clone(),
+            remaining: self.remaining,
+        }
+    }
+}
+
+impl<T> Clone for RawIterHash<'_, T> {
+    fn clone(&self) -> Self {
+        RawIterHash {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+pub(crate) fn empty_buckets<T>(len: usize) -> Buckets<T> {
+    (0..len).map(|_| Vec::new()).collect()
+}
+
+// Empty chains don't allocate, so the array itself is the only allocation that can fail
+fn try_empty_buckets<T>(len: usize) -> Result<Buckets<T>, TryReserveError> {
+    let layout = Layout::array::<Bucket<T>>(len).map_err(|_| TryReserveError::CapacityOverflow)?;
+
+    let mut buckets = Vec::new();
+    buckets
+        .try_reserve_exact(len)
+        .map_err(|_| TryReserveError::AllocError { layout })?;
+    buckets.resize_with(len, Vec::new);
+
+    Ok(buckets)
+}
+
+// Position of the first value in a chain that `search` accepts, found through the
+// chain's tree bin when it has one
+fn chain_position<T, S: Search<T>>(
+    chain: &[T],
+    tree_bin: Option<&TreeBin>,
+    hash: u64,
+    search: &mut S,
+) -> Option<usize> {
+    let Some(tree_bin) = tree_bin else {
+        return chain.iter().position(|value| search.eq(value));
+    };
+    let run = tree_bin.positions(hash);
+
+    if S::ORDERED {
+        let slot = run
+            .binary_search_by(|&position| search.cmp(&chain[position]).reverse())
+            .ok()?;
+        Some(run[slot]).filter(|&position| search.eq(&chain[position]))
+    } else {
+        run.iter()
+            .copied()
+            .find(|&position| search.eq(&chain[position]))
+    }
+}
+
+// `Vec::push` on a chain, giving it a tree bin once it got too long
+fn chain_push<T>(
+    chain: &mut Bucket<T>,
+    tree_bins: &mut TreeBins,
+    index: usize,
+    hash: u64,
+    value: T,
+    hasher: &impl Fn(&T) -> u64,
+    order: Order<T>,
+) -> usize {
+    chain.push(value);
+
+    match tree_bins.get_mut(&index) {
+        Some(tree_bin) => tree_bin.push(hash, chain, order),
+        None if chain.len() > TREEIFY_THRESHOLD => {
+            tree_bins.insert(index, TreeBin::new(chain, hasher, order));
+        }
+        None => {}
+    }
+
+    chain.len() - 1
+}
+
+// `Vec::swap_remove` on a chain, dropping its tree bin once it got short again
+fn chain_swap_remove<T>(
+    chain: &mut Bucket<T>,
+    tree_bins: &mut TreeBins,
+    index: usize,
+    position: usize,
+    order: Order<T>,
+) -> T {
+    if let Some(tree_bin) = tree_bins.get_mut(&index) {
+        tree_bin.swap_remove(position, chain, order);
+
+        if tree_bin.len() < UNTREEIFY_THRESHOLD {
+            tree_bins.remove(&index);
+        }
+    }
+
+    chain.swap_remove(position)
+}
+
+fn retain_chains<T>(
+    buckets: &mut [Bucket<T>],
+    tree_bins: &mut TreeBins,
+    size: &mut usize,
+    f: &mut impl FnMut(&mut T) -> bool,
+    order: Order<T>,
+) {
+    for (index, chain) in buckets.iter_mut().enumerate() {
+        let mut position = 0;
+
+        while position < chain.len() {
+            if f(&mut chain[position]) {
+                position += 1;
+            } else {
+                // Removed and counted one at a time: a panicking `f` leaves size exact
+                chain_swap_remove(chain, tree_bins, index, position, order);
+                *size -= 1;
+            }
+        }
+    }
+}
+
+fn try_reserve_chain<T>(bucket: &mut Bucket<T>) -> Result<(), TryReserveError> {
+    bucket
+        .try_reserve(1)
+        .map_err(|_| match Layout::array::<T>(bucket.len() + 1) {
+            Ok(layout) => TryReserveError::AllocError { layout },
+            Err(_) => TryReserveError::CapacityOverflow,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::RandomState;
+    use std::hash::BuildHasher;
+
+    use super::*;
+
+    #[test]
+    fn test_custom_equivalence() {
+        // Case-insensitive interner: hash and compare the lowercased string
+        let hash_builder = RandomState::new();
+        let hash = |s: &str| hash_builder.hash_one(s.to_lowercase());
+        let mut interner: RawTable<String> = RawTable::new();
+
+        for word in ["Apple", "banana", "APPLE", "Banana", "cherry"] {
+            let h = hash(word);
+            if interner.find(h, |s| s.eq_ignore_ascii_case(word)).is_none() {
+                interner.insert(h, word.to_string(), |s| hash(s));
+            }
+        }
+
+        assert_eq!(interner.len(), 3);
+        assert_eq!(
+            interner.find(hash("apple"), |s| s.eq_ignore_ascii_case("apple")),
+            Some(&"Apple".to_string())
+        );
+        assert_eq!(
+            interner.remove_entry(hash("BANANA"), |s| s.eq_ignore_ascii_case("banana")),
+            Some("banana".to_string())
+        );
+        assert_eq!(interner.iter().len(), 2);
+    }
+
+    #[test]
+    fn test_mid_rehash() {
+        let hash = |value: &u64| value.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        let mut raw_table: RawTable<u64> = RawTable::with_buckets(8);
+        raw_table.set_growth_policy(GrowthPolicy {
+            rehash: RehashMode::Incremental {
+                buckets_per_step: 1,
+            },
+            ..GrowthPolicy::default()
+        });
+
+        for i in 0..7 {
+            raw_table.insert(hash(&i), i, hash);
+        }
+
+        assert!(raw_table.is_rehashing());
+
+        // Values still in the old array are found, changed and removed in place
+        for i in 0..7 {
+            assert!(raw_table.iter_hash(hash(&i)).any(|&value| value == i));
+            *raw_table.find_mut(hash(&i), |&value| value == i).unwrap() += 100;
+        }
+
+        assert_eq!(
+            raw_table.remove_entry(hash(&0), |&value| value == 100),
+            Some(100)
+        );
+        assert_eq!(
+            raw_table.remove_entry(hash(&0), |&value| value == 100),
+            None
+        );
+        assert_eq!(raw_table.len(), 6);
+        assert_eq!(raw_table.drain().sum::<u64>(), (101..107).sum());
+        assert!(raw_table.is_empty());
+    }
+
+    #[test]
+    fn test_bounded_rehash_work() {
+        // Counts values the hasher closure gets called for: every move to the new
+        // array hashes one
+        let spread = |value: &u64| value.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        let moved = std::cell::Cell::new(0);
+        let hash = |value: &u64| {
+            moved.set(moved.get() + 1);
+            spread(value)
+        };
+        let mut raw_table: RawTable<u64> = RawTable::with_buckets(8);
+        raw_table.set_growth_policy(GrowthPolicy {
+            rehash: RehashMode::Incremental {
+                buckets_per_step: 1,
+            },
+            ..GrowthPolicy::default()
+        });
+
+        let mut resizes = 0;
+        for i in 0..200_000u64 {
+            let len = raw_table.num_buckets();
+            moved.set(0);
+
+            raw_table.insert(spread(&i), i, hash);
+
+            // A couple of short old chains per write, however many resizes came due;
+            // finishing a resize in one go would move up to half the values
+            assert!(
+                moved.get() <= 8,
+                "insert {} moved {} values",
+                i,
+                moved.get()
+            );
+            if raw_table.num_buckets() != len {
+                resizes += 1;
+            }
+
+            // Each resize lands before the load reaches the limit again
+            assert!(!raw_table
+                .policy
+                .exceeds_load(raw_table.len(), raw_table.num_buckets()));
+        }
+
+        assert!(resizes >= 10);
+        for i in (0..200_000).step_by(1000) {
+            assert!(raw_table.find(spread(&i), |&value| value == i).is_some());
+        }
+    }
+}
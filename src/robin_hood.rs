@@ -0,0 +1,317 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+
+use crate::KV;
+
+// Robin Hood keeps probe lengths short enough to run this close to full
+const MAX_LOAD_FACTOR: f64 = 0.9;
+
+#[derive(Clone, Debug)]
+struct Entry<K, V> {
+    kv: KV<K, V>,
+    // How far the entry sits from its home slot
+    distance: usize,
+}
+
+/// Linear probing table with Robin Hood insertion: an entry that is further
+/// from home than the one occupying a slot takes that slot over, and the
+/// displaced ("richer") entry continues probing. Deletion shifts the following
+/// entries back instead of leaving tombstones.
+#[derive(Clone, Debug)]
+pub struct RobinHoodTable<K, V, S = RandomState> {
+    slots: Vec<Option<Entry<K, V>>>,
+    size: usize,
+    hash_builder: S,
+}
+
+impl<K, V> RobinHoodTable<K, V, RandomState>
+where
+    K: Hash + Eq,
+{
+    pub fn new(with_capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(with_capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> RobinHoodTable<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn with_capacity_and_hasher(with_capacity: usize, hash_builder: S) -> Self {
+        let len = with_capacity.max(1).next_power_of_two();
+
+        RobinHoodTable {
+            slots: Self::empty_slots(len),
+            size: 0,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if (self.size + 1) as f64 > self.slots.len() as f64 * MAX_LOAD_FACTOR {
+            self.resize();
+        }
+
+        let index = self.create_index(&key);
+        let mut entry = Entry {
+            kv: KV::new(key, value),
+            distance: 0,
+        };
+        // Once an entry has been displaced it can't match anything further along
+        let mut displaced = false;
+
+        for i in 0..self.slots.len() {
+            let index = (index + i) & self.mask();
+
+            match &mut self.slots[index] {
+                None => {
+                    self.slots[index] = Some(entry);
+                    self.size += 1;
+                    return None;
+                }
+                Some(current) if !displaced && current.kv.key == entry.kv.key => {
+                    return Some(mem::replace(&mut current.kv.value, entry.kv.value));
+                }
+                Some(current) => {
+                    if current.distance < entry.distance {
+                        mem::swap(current, &mut entry);
+                        displaced = true;
+                    }
+                }
+            }
+
+            entry.distance += 1;
+        }
+
+        unreachable!("load factor keeps at least one free slot")
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key)
+            .and_then(|index| self.slots[index].as_ref())
+            .map(|entry| &entry.kv.value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key)
+            .and_then(|index| self.slots[index].as_mut())
+            .map(|entry| &mut entry.kv.value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    pub fn delete<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut index = self.find(key)?;
+        let removed = self.slots[index].take().map(|entry| entry.kv.value);
+        self.size -= 1;
+
+        // Backward shift: pull the rest of the cluster one slot closer to home
+        loop {
+            let next = (index + 1) & self.mask();
+
+            match self.slots[next].take() {
+                Some(mut entry) if entry.distance > 0 => {
+                    entry.distance -= 1;
+                    self.slots[index] = Some(entry);
+                    index = next;
+                }
+                entry => {
+                    self.slots[next] = entry;
+                    break;
+                }
+            }
+        }
+
+        removed
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.create_index(key);
+
+        for distance in 0..self.slots.len() {
+            let index = (index + distance) & self.mask();
+
+            match &self.slots[index] {
+                // An entry closer to home than we'd be means the key isn't here
+                Some(entry) if entry.distance < distance => return None,
+                Some(entry) if entry.kv.key.borrow() == key => return Some(index),
+                Some(_) => {}
+                None => return None,
+            }
+        }
+
+        None
+    }
+
+    fn create_index<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        let hash = self.hash_builder.hash_one(key);
+
+        // Length is a power of two, so masking keeps the low bits uniformly
+        (hash as usize) & self.mask()
+    }
+
+    fn mask(&self) -> usize {
+        self.slots.len() - 1
+    }
+
+    fn resize(&mut self) {
+        let new_len = self.slots.len() * 2;
+        let old_slots = mem::replace(&mut self.slots, Self::empty_slots(new_len));
+        self.size = 0;
+
+        for entry in old_slots.into_iter().flatten() {
+            self.insert(entry.kv.key, entry.kv.value);
+        }
+    }
+
+    fn empty_slots(len: usize) -> Vec<Option<Entry<K, V>>> {
+        (0..len).map(|_| None).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashers::FxBuildHasher;
+
+    #[test]
+    fn test_shared_home_slot() {
+        // Under FxHash every one of these keys' home is slot 0 (see `FxHasher`),
+        // so they form one cluster ordered by probe distance
+        let mut hash_table =
+            RobinHoodTable::with_capacity_and_hasher(128, FxBuildHasher::default());
+
+        for i in 0..100u64 {
+            assert_eq!(hash_table.insert(i << 32, i), None);
+        }
+
+        assert_eq!(hash_table.len(), 100);
+        assert_eq!(hash_table.insert(5 << 32, 500), Some(5));
+        *hash_table.get_mut(&(7 << 32)).unwrap() = 700;
+
+        for (index, slot) in hash_table.slots.iter().enumerate() {
+            assert_eq!(
+                slot.as_ref().map(|entry| entry.distance),
+                (index < 100).then_some(index)
+            );
+        }
+
+        for i in (0..100u64).step_by(2) {
+            assert_eq!(hash_table.delete(&(i << 32)), Some(i));
+        }
+
+        for i in 0..100u64 {
+            let expected = match i {
+                _ if i % 2 == 0 => None,
+                5 => Some(500),
+                7 => Some(700),
+                _ => Some(i),
+            };
+            assert_eq!(hash_table.get(&(i << 32)).copied(), expected);
+        }
+    }
+
+    #[test]
+    fn test_borrowed_backward_shift_delete() {
+        let mut hash_table = RobinHoodTable::with_capacity_and_hasher(64, FxBuildHasher::default());
+
+        for i in 0..50 {
+            hash_table.insert(format!("key_{i}"), i);
+        }
+
+        // An entry whose successor sits away from home, so deleting it shifts that one back
+        let index = (0..hash_table.slots.len())
+            .find(|&index| {
+                hash_table.slots[index].is_some()
+                    && hash_table.slots[(index + 1) & hash_table.mask()]
+                        .as_ref()
+                        .is_some_and(|entry| entry.distance > 0)
+            })
+            .expect("50 keys in 64 slots leave a cluster");
+
+        let key = hash_table.slots[index].as_ref().unwrap().kv.key.clone();
+        let next = hash_table.slots[(index + 1) & hash_table.mask()]
+            .as_ref()
+            .unwrap();
+        let (next_key, next_distance) = (next.kv.key.clone(), next.distance);
+
+        assert!(hash_table.delete(key.as_str()).is_some());
+        assert!(!hash_table.contains_key(key.as_str()));
+
+        let shifted = hash_table.slots[index].as_ref().unwrap();
+        assert_eq!(shifted.kv.key, next_key);
+        assert_eq!(shifted.distance, next_distance - 1);
+        assert!(hash_table.get(next_key.as_str()).is_some());
+        assert_eq!(hash_table.len(), 49);
+    }
+
+    #[test]
+    fn test_backward_shift_delete() {
+        let mut hash_table = RobinHoodTable::new(128);
+
+        // Close to the 0.9 limit, so clusters are long
+        for i in 0..115 {
+            hash_table.insert(i, i);
+        }
+
+        for i in (0..115).filter(|i| i % 3 == 0) {
+            assert_eq!(hash_table.delete(&i), Some(i));
+        }
+
+        assert_eq!(hash_table.delete(&0), None);
+
+        for i in 0..115 {
+            let expected = if i % 3 == 0 { None } else { Some(&i) };
+            assert_eq!(hash_table.get(&i), expected);
+        }
+
+        // No tombstones: every occupied slot is still reachable from its home
+        for (index, slot) in hash_table.slots.iter().enumerate() {
+            if let Some(entry) = slot {
+                let home = hash_table.create_index(&entry.kv.key);
+                assert_eq!((home + entry.distance) & hash_table.mask(), index);
+            }
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use std::hash::{BuildHasher, Hash, Hasher};
+
+use crate::hashers::SipHasher13;
+
+/// Version of the byte encoding `StableHash` impls feed into `StableHasher`.
+///
+/// Every hash starts with it, so bumping it changes all of them at once. Anything
+/// persisted or shared between processes should record the version it was built with.
+pub const STABLE_HASH_VERSION: u32 = 1;
+
+// Fixed for good: they're part of every stable hash ever computed
+const K0: u64 = 0x7374_6162_6c65_5f6b;
+const K1: u64 = 0x6861_7368_5f74_626c;
+
+/// Hashing that gives the same `u64` on every platform, process and Rust release.
+///
+/// Unlike `Hash`, whose output std may change between releases and which hashes
+/// integers in native byte order, implementations fix a byte encoding (version 1):
+///
+/// - integers as little endian, `usize` and `isize` widened to 64 bits
+/// - `bool` as one byte, `char` as a `u32`, floats by their bit pattern
+/// - `str` and slices as a `u64` length followed by their bytes or elements
+/// - `Option` as a `0` byte, or a `1` byte followed by the value
+/// - tuples as their fields in order
+///
+/// Impls for other types should only combine these, field by field.
+pub trait StableHash {
+    fn stable_hash(&self, state: &mut StableHasher);
+}
+
+/// SipHash-1-3 under fixed keys, fed the encoding version first. Integer writes
+/// are little endian, so even `Hash` impls hash the same across byte orders.
+#[derive(Clone, Debug)]
+pub struct StableHasher {
+    sip: SipHasher13,
+}
+
+impl StableHasher {
+    pub fn new() -> Self {
+        let mut sip = SipHasher13::new_with_keys(K0, K1);
+        sip.write(&STABLE_HASH_VERSION.to_le_bytes());
+
+        StableHasher { sip }
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.sip.write(bytes);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.sip.finish()
+    }
+}
+
+// The stable hash of a single value
+pub fn stable_hash<T: StableHash + ?Sized>(value: &T) -> u64 {
+    let mut state = StableHasher::new();
+    value.stable_hash(&mut state);
+    state.finish()
+}
+
+/// Builds `StableHasher`s, for tables whose layout must come out the same everywhere:
+/// `HashTable<Stable<K>, V, StableBuildHasher>`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StableBuildHasher;
+
+impl BuildHasher for StableBuildHasher {
+    type Hasher = StableHasher;
+
+    fn build_hasher(&self) -> StableHasher {
+        StableHasher::new()
+    }
+}
+
+/// Key wrapper whose `Hash` goes through `StableHash`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Stable<T>(pub T);
+
+impl<T: StableHash> Hash for Stable<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(stable_hash(&self.0));
+    }
+}
+
+macro_rules! impl_stable_hash_int {
+    ($($ty:ty => $write:ident),* $(,)?) => {
+        $(
+            impl StableHash for $ty {
+                fn stable_hash(&self, state: &mut StableHasher) {
+                    state.$write(*self as _);
+                }
+            }
+        )*
+    };
+}
+
+impl_stable_hash_int!(
+    u8 => write_u8,
+    u16 => write_u16,
+    u32 => write_u32,
+    u64 => write_u64,
+    u128 => write_u128,
+    usize => write_u64,
+    i8 => write_u8,
+    i16 => write_u16,
+    i32 => write_u32,
+    i64 => write_u64,
+    i128 => write_u128,
+    isize => write_u64,
+);
+
+impl StableHash for bool {
+    fn stable_hash(&self, state: &mut StableHasher) {
+        state.write_u8(*self as u8);
+    }
+}
+
+impl StableHash for char {
+    fn stable_hash(&self, state: &mut StableHasher) {
+        state.write_u32(*self as u32);
+    }
+}
+
+// By bit pattern: 0.0 and -0.0 differ, and so do NaNs with different payloads
+impl StableHash for f32 {
+    fn stable_hash(&self, state: &mut StableHasher) {
+        state.write_u32(self.to_bits());
+    }
+}
+
+impl StableHash for f64 {
+    fn stable_hash(&self, state: &mut StableHasher) {
+        state.write_u64(self.to_bits());
+    }
+}
+
+impl StableHash for str {
+    fn stable_hash(&self, state: &mut StableHasher) {
+        state.write_u64(self.len() as u64);
+        state.write(self.as_bytes());
+    }
+}
+
+impl StableHash for String {
+    fn stable_hash(&self, state: &mut StableHasher) {
+        self.as_str().stable_hash(state);
+    }
+}
+
+impl<T: StableHash> StableHash for [T] {
+    fn stable_hash(&self, state: &mut StableHasher) {
+        state.write_u64(self.len() as u64);
+        for value in self {
+            value.stable_hash(state);
+        }
+    }
+}
+
+impl<T: StableHash> StableHash for Vec<T> {
+    fn stable_hash(&self, state: &mut StableHasher) {
+        self.as_slice().stable_hash(state);
+    }
+}
+
+impl<T: StableHash> StableHash for Option<T> {
+    fn stable_hash(&self, state: &mut StableHasher) {
+        match self {
+            None => state.write_u8(0),
+            Some(value) => {
+                state.write_u8(1);
+                value.stable_hash(state);
+            }
+        }
+    }
+}
+
+impl<T: StableHash + ?Sized> StableHash for &T {
+    fn stable_hash(&self, state: &mut StableHasher) {
+        (**self).stable_hash(state);
+    }
+}
+
+macro_rules! impl_stable_hash_tuple {
+    ($($name:ident)*) => {
+        impl<$($name: StableHash),*> StableHash for ($($name,)*) {
+            #[allow(non_snake_case, unused_variables)]
+            fn stable_hash(&self, state: &mut StableHasher) {
+                let ($($name,)*) = self;
+                $($name.stable_hash(state);)*
+            }
+        }
+    };
+}
+
+impl_stable_hash_tuple!();
+impl_stable_hash_tuple!(A);
+impl_stable_hash_tuple!(A B);
+impl_stable_hash_tuple!(A B C);
+impl_stable_hash_tuple!(A B C D);
+impl_stable_hash_tuple!(A B C D E);
+impl_stable_hash_tuple!(A B C D E F);
+impl_stable_hash_tuple!(A B C D E F G);
+impl_stable_hash_tuple!(A B C D E F G H);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HashTable;
+
+    // SipHash-1-3 over the version and then `bytes`, the way the encoding promises
+    fn hash_encoding(bytes: &[u8]) -> u64 {
+        let mut sip = SipHasher13::new_with_keys(K0, K1);
+        sip.write(&[1, 0, 0, 0]);
+        sip.write(bytes);
+        sip.finish()
+    }
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(stable_hash(&0x0102u16), hash_encoding(&[2, 1]));
+        assert_eq!(stable_hash(&-1i8), hash_encoding(&[0xff]));
+        assert_eq!(stable_hash(&7usize), stable_hash(&7u64));
+        assert_eq!(stable_hash(&'a'), hash_encoding(&[0x61, 0, 0, 0]));
+        assert_eq!(stable_hash(&1.0f32), hash_encoding(&[0, 0, 0x80, 0x3f]));
+
+        assert_eq!(
+            stable_hash(&(true, "ab")),
+            hash_encoding(&[1, 2, 0, 0, 0, 0, 0, 0, 0, b'a', b'b'])
+        );
+        assert_eq!(
+            stable_hash(&vec![Some(3u8), None]),
+            hash_encoding(&[2, 0, 0, 0, 0, 0, 0, 0, 1, 3, 0])
+        );
+
+        // Length prefixes keep neighbouring strings apart
+        assert_ne!(stable_hash(&("ab", "c")), stable_hash(&("a", "bc")));
+    }
+
+    #[test]
+    fn test_pinned_hashes() {
+        // These must never change without bumping `STABLE_HASH_VERSION`
+        assert_eq!(stable_hash(&42u64), 0xc625_7676_2666_4494);
+        assert_eq!(stable_hash("account_7"), 0x04b7_08f3_659a_2883);
+        assert_eq!(
+            stable_hash(&(1u32, String::from("x"), Some(-2i64))),
+            0xc481_bd08_e20e_79ff
+        );
+    }
+
+    #[test]
+    fn test_stable_layout() {
+        let build = || {
+            let mut hash_table: HashTable<Stable<String>, i32, _> =
+                HashTable::with_hasher(StableBuildHasher);
+            for i in 0..100 {
+                hash_table.insert(Stable(format!("key_{}", i)), i);
+            }
+            hash_table
+        };
+
+        // Same hashes, so the same buckets in the same order
+        let first: Vec<_> = build().into_iter().collect();
+        let second: Vec<_> = build().into_iter().collect();
+        assert_eq!(first, second);
+
+        assert_eq!(build().get(&Stable("key_7".to_string())), Some(&7));
+    }
+}
@@ -0,0 +1,523 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+
+use crate::KV;
+
+// Slots scanned per control-byte group
+const GROUP_WIDTH: usize = 16;
+
+// Control bytes: top bit set -> special, top bit clear -> full slot holding a 7 bit fingerprint
+const EMPTY: u8 = 0b1111_1111;
+const DELETED: u8 = 0b1000_0000;
+
+// Max load factor 7/8, same as SwissTable / hashbrown
+const MAX_LOAD_NUMERATOR: usize = 7;
+const MAX_LOAD_DENOMINATOR: usize = 8;
+
+/// Open addressing table modelled on SwissTable: a separate control-byte array
+/// holds a 7 bit fingerprint (`h2`) of each occupied slot, and lookups compare a
+/// whole 16 slot group of control bytes at once, touching the slots themselves
+/// only for fingerprint matches.
+#[derive(Clone, Debug)]
+pub struct SwissTable<K, V, S = RandomState> {
+    ctrl: Vec<u8>,
+    slots: Vec<Option<KV<K, V>>>,
+    size: usize,
+    deleted: usize,
+    hash_builder: S,
+}
+
+impl<K, V> SwissTable<K, V, RandomState>
+where
+    K: Hash + Eq,
+{
+    pub fn new(with_capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(with_capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> SwissTable<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn with_capacity_and_hasher(with_capacity: usize, hash_builder: S) -> Self {
+        let slots = with_capacity * MAX_LOAD_DENOMINATOR / MAX_LOAD_NUMERATOR;
+        let len = slots.div_ceil(GROUP_WIDTH).max(1).next_power_of_two() * GROUP_WIDTH;
+
+        SwissTable {
+            ctrl: vec![EMPTY; len],
+            slots: Self::empty_slots(len),
+            size: 0,
+            deleted: 0,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.hash_builder.hash_one(&key);
+
+        if let Some(index) = self.find(hash, &key) {
+            let kv = self.slots[index].as_mut().expect("full control byte");
+            return Some(mem::replace(&mut kv.value, value));
+        }
+
+        if (self.size + self.deleted + 1) * MAX_LOAD_DENOMINATOR
+            > self.slots.len() * MAX_LOAD_NUMERATOR
+        {
+            self.resize();
+        }
+
+        let index = self.find_insert_slot(hash);
+        if self.ctrl[index] == DELETED {
+            self.deleted -= 1;
+        }
+
+        self.ctrl[index] = h2(hash);
+        self.slots[index] = Some(KV::new(key, value));
+        self.size += 1;
+
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(self.hash_builder.hash_one(key), key)?;
+        self.slots[index].as_ref().map(|kv| &kv.value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(self.hash_builder.hash_one(key), key)?;
+        self.slots[index].as_mut().map(|kv| &mut kv.value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(self.hash_builder.hash_one(key), key).is_some()
+    }
+
+    pub fn delete<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(self.hash_builder.hash_one(key), key)?;
+        let group_start = index - index % GROUP_WIDTH;
+
+        // Probing stops at the first group that has an empty slot, so if this group
+        // already has one no lookup ever walked past it and the slot can become empty
+        // again. Otherwise a tombstone keeps later probe chains intact.
+        if Group::load(&self.ctrl[group_start..]).match_empty().any() {
+            self.ctrl[index] = EMPTY;
+        } else {
+            self.ctrl[index] = DELETED;
+            self.deleted += 1;
+        }
+
+        self.size -= 1;
+        self.slots[index].take().map(|kv| kv.value)
+    }
+
+    fn find<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let fingerprint = h2(hash);
+
+        for group_start in self.probe_sequence(hash) {
+            let group = Group::load(&self.ctrl[group_start..]);
+
+            for bit in group.match_byte(fingerprint) {
+                let index = group_start + bit;
+                if self.slots[index]
+                    .as_ref()
+                    .is_some_and(|kv| kv.key.borrow() == key)
+                {
+                    return Some(index);
+                }
+            }
+
+            if group.match_empty().any() {
+                return None;
+            }
+        }
+
+        None
+    }
+
+    fn find_insert_slot(&self, hash: u64) -> usize {
+        self.probe_sequence(hash)
+            .find_map(|group_start| {
+                let group = Group::load(&self.ctrl[group_start..]);
+                group
+                    .match_empty_or_deleted()
+                    .next()
+                    .map(|bit| group_start + bit)
+            })
+            .expect("load factor keeps at least one free slot")
+    }
+
+    // Triangular probing over whole groups; visits every group once when the group count is a power of two
+    fn probe_sequence(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let groups = self.slots.len() / GROUP_WIDTH;
+        let mask = groups - 1;
+        let start = h1(hash) as usize;
+
+        (0..groups).map(move |i| (start.wrapping_add(i * (i + 1) / 2) & mask) * GROUP_WIDTH)
+    }
+
+    fn resize(&mut self) {
+        let groups = self.slots.len() / GROUP_WIDTH;
+        // Plenty of tombstones: rehashing in place is enough to free them up
+        let groups = if self.size * 2 < self.slots.len() * MAX_LOAD_NUMERATOR / MAX_LOAD_DENOMINATOR
+        {
+            groups
+        } else {
+            groups * 2
+        };
+
+        let len = groups * GROUP_WIDTH;
+        self.ctrl = vec![EMPTY; len];
+        let old_slots = mem::replace(&mut self.slots, Self::empty_slots(len));
+        self.size = 0;
+        self.deleted = 0;
+
+        for kv in old_slots.into_iter().flatten() {
+            let hash = self.hash_builder.hash_one(&kv.key);
+            let index = self.find_insert_slot(hash);
+
+            self.ctrl[index] = h2(hash);
+            self.slots[index] = Some(kv);
+            self.size += 1;
+        }
+    }
+
+    fn empty_slots(len: usize) -> Vec<Option<KV<K, V>>> {
+        (0..len).map(|_| None).collect()
+    }
+}
+
+// Group selection bits
+fn h1(hash: u64) -> u64 {
+    hash >> 7
+}
+
+// 7 bit fingerprint stored in the control byte
+fn h2(hash: u64) -> u8 {
+    (hash & 0x7f) as u8
+}
+
+/// Bit `i` set -> slot `i` of the group matched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct BitMask(u16);
+
+impl BitMask {
+    fn any(self) -> bool {
+        self.0 != 0
+    }
+}
+
+impl Iterator for BitMask {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let bit = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(bit)
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+use sse2::Group;
+
+#[cfg(not(all(target_arch = "x86_64", target_feature = "sse2")))]
+use generic::Group;
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+mod sse2 {
+    use std::arch::x86_64::{
+        __m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8, _mm_set1_epi8,
+    };
+
+    use super::{BitMask, EMPTY, GROUP_WIDTH};
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Group(__m128i);
+
+    impl Group {
+        pub(super) fn load(ctrl: &[u8]) -> Self {
+            assert!(ctrl.len() >= GROUP_WIDTH);
+            // SAFETY: the assert above keeps the unaligned 16 byte load in bounds,
+            // and SSE2 is part of the x86_64 baseline
+            unsafe { Group(_mm_loadu_si128(ctrl.as_ptr() as *const __m128i)) }
+        }
+
+        pub(super) fn match_byte(self, byte: u8) -> BitMask {
+            // SAFETY: SSE2 is part of the x86_64 baseline
+            unsafe {
+                let cmp = _mm_cmpeq_epi8(self.0, _mm_set1_epi8(byte as i8));
+                BitMask(_mm_movemask_epi8(cmp) as u16)
+            }
+        }
+
+        pub(super) fn match_empty(self) -> BitMask {
+            self.match_byte(EMPTY)
+        }
+
+        pub(super) fn match_empty_or_deleted(self) -> BitMask {
+            // Special control bytes are exactly the ones with the top bit set
+            // SAFETY: SSE2 is part of the x86_64 baseline
+            unsafe { BitMask(_mm_movemask_epi8(self.0) as u16) }
+        }
+    }
+}
+
+// Portable fallback: the group is handled as two u64 words with SWAR bit tricks
+#[cfg(any(test, not(all(target_arch = "x86_64", target_feature = "sse2"))))]
+mod generic {
+    use super::{BitMask, GROUP_WIDTH};
+
+    const LO: u64 = 0x0101_0101_0101_0101;
+    const HI: u64 = 0x8080_8080_8080_8080;
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Group([u64; 2]);
+
+    impl Group {
+        pub(super) fn load(ctrl: &[u8]) -> Self {
+            let word = |start: usize| {
+                u64::from_le_bytes(ctrl[start..start + 8].try_into().expect("8 control bytes"))
+            };
+
+            Group([word(0), word(GROUP_WIDTH / 2)])
+        }
+
+        pub(super) fn match_byte(self, byte: u8) -> BitMask {
+            self.map(|word| {
+                // Exact zero byte detection: no false positives carried from lower bytes
+                let x = word ^ (LO * byte as u64);
+                !(((x & !HI).wrapping_add(!HI)) | x | !HI)
+            })
+        }
+
+        pub(super) fn match_empty(self) -> BitMask {
+            // EMPTY is the only control byte with both of the top two bits set
+            self.map(|word| word & (word << 1) & HI)
+        }
+
+        pub(super) fn match_empty_or_deleted(self) -> BitMask {
+            self.map(|word| word & HI)
+        }
+
+        // `f` leaves 0x80 in every matching byte; pack those top bits into a BitMask
+        fn map(self, f: impl Fn(u64) -> u64) -> BitMask {
+            let [lo, hi] = self.0.map(|word| pack(f(word)));
+            BitMask(lo | hi << 8)
+        }
+    }
+
+    fn pack(word: u64) -> u16 {
+        // Gathers bit 7 of every byte into the top byte (movemask emulation)
+        ((word & HI).wrapping_mul(0x0002_0408_1020_4081) >> 56) as u16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashers::FxBuildHasher;
+
+    #[test]
+    fn test_shared_fingerprint() {
+        // Under FxHash these keys all get fingerprint 0 and the same first group
+        // (see `FxHasher`), so every probed slot is a fingerprint match that has to
+        // be told apart by the key
+        let mut hash_table = SwissTable::with_capacity_and_hasher(0, FxBuildHasher::default());
+
+        for i in 0..200u64 {
+            assert_eq!(hash_table.insert(i << 32, i), None);
+        }
+
+        assert_eq!(hash_table.len(), 200);
+        assert_eq!(hash_table.insert(9 << 32, 900), Some(9));
+        *hash_table.get_mut(&(10 << 32)).unwrap() = 1000;
+
+        for i in 0..200u64 {
+            let expected = match i {
+                9 => 900,
+                10 => 1000,
+                _ => i,
+            };
+            assert_eq!(hash_table.get(&(i << 32)), Some(&expected));
+        }
+
+        assert!(!hash_table.contains_key(&(200u64 << 32)));
+    }
+
+    #[test]
+    fn test_borrowed_lookup_through_generic_group() {
+        let mut hash_table = SwissTable::with_capacity_and_hasher(0, FxBuildHasher::default());
+
+        for i in 0..300 {
+            hash_table.insert(format!("key_{i}"), i);
+        }
+        for i in (0..300).step_by(5) {
+            hash_table.delete(format!("key_{i}").as_str());
+        }
+
+        // `find` with the portable SWAR group, whatever `Group` is on this target
+        let generic_find = |key: &str| {
+            let hash = hash_table.hash_builder.hash_one(key);
+
+            for group_start in hash_table.probe_sequence(hash) {
+                let group = generic::Group::load(&hash_table.ctrl[group_start..]);
+
+                for bit in group.match_byte(h2(hash)) {
+                    let index = group_start + bit;
+                    if hash_table.slots[index]
+                        .as_ref()
+                        .is_some_and(|kv| kv.key == key)
+                    {
+                        return Some(index);
+                    }
+                }
+
+                if group.match_empty().any() {
+                    return None;
+                }
+            }
+
+            None
+        };
+
+        for i in 0..301 {
+            let key = format!("key_{i}");
+            let hash = hash_table.hash_builder.hash_one(key.as_str());
+            let index = generic_find(&key);
+
+            assert_eq!(index, hash_table.find(hash, key.as_str()));
+            assert_eq!(index.is_some(), i % 5 != 0 && i < 300);
+            assert_eq!(
+                index.map(|index| &hash_table.slots[index].as_ref().unwrap().value),
+                hash_table.get(key.as_str())
+            );
+        }
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut hash_table = SwissTable::new(64);
+
+        for i in 0..500 {
+            hash_table.insert(i, i);
+        }
+
+        for i in (0..500).step_by(2) {
+            assert_eq!(hash_table.delete(&i), Some(i));
+        }
+
+        assert_eq!(hash_table.size(), 250);
+        assert_eq!(hash_table.delete(&0), None);
+
+        for i in 0..500 {
+            let expected = if i % 2 == 0 { None } else { Some(&i) };
+            assert_eq!(hash_table.get(&i), expected);
+        }
+
+        // Reuse the freed slots
+        for i in (0..500).step_by(2) {
+            hash_table.insert(i, i + 1);
+        }
+
+        assert_eq!(hash_table.get(&10), Some(&11));
+        assert_eq!(hash_table.size(), 500);
+    }
+
+    #[test]
+    fn test_generic_group_matches() {
+        let mut ctrl = [EMPTY; GROUP_WIDTH];
+        ctrl[1] = 0x15;
+        ctrl[3] = DELETED;
+        ctrl[8] = 0x15;
+        ctrl[9] = 0x14;
+        ctrl[15] = 0x00;
+
+        let group = generic::Group::load(&ctrl);
+
+        assert_eq!(group.match_byte(0x15).collect::<Vec<_>>(), vec![1, 8]);
+        assert_eq!(group.match_byte(0x00).collect::<Vec<_>>(), vec![15]);
+        assert_eq!(group.match_byte(0x7f).collect::<Vec<_>>(), vec![]);
+        assert_eq!(
+            group.match_empty().collect::<Vec<_>>(),
+            vec![0, 2, 4, 5, 6, 7, 10, 11, 12, 13, 14]
+        );
+        assert_eq!(
+            group.match_empty_or_deleted().collect::<Vec<_>>(),
+            vec![0, 2, 3, 4, 5, 6, 7, 10, 11, 12, 13, 14]
+        );
+    }
+
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    #[test]
+    fn test_sse2_agrees_with_generic() {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+
+        for _ in 0..1000 {
+            let mut ctrl = [0u8; GROUP_WIDTH];
+            for byte in ctrl.iter_mut() {
+                // xorshift, biased towards special control bytes
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                *byte = match state % 4 {
+                    0 => EMPTY,
+                    1 => DELETED,
+                    _ => (state >> 8) as u8 & 0x7f,
+                };
+            }
+
+            let fast = sse2::Group::load(&ctrl);
+            let portable = generic::Group::load(&ctrl);
+
+            assert_eq!(fast.match_byte(ctrl[0]), portable.match_byte(ctrl[0]));
+            assert_eq!(fast.match_empty(), portable.match_empty());
+            assert_eq!(
+                fast.match_empty_or_deleted(),
+                portable.match_empty_or_deleted()
+            );
+        }
+    }
+}
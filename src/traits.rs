@@ -0,0 +1,190 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::fmt::{self, Debug};
+use std::hash::{BuildHasher, Hash};
+use std::ops::Index;
+
+use crate::{HashCache, HashTable, KeyOrder};
+
+impl<K, V, S, H, O> Default for HashTable<K, V, S, H, O>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+    H: HashCache,
+    O: KeyOrder<K>,
+{
+    fn default() -> Self {
+        HashTable::with_hasher(S::default())
+    }
+}
+
+// Map-style `{k: v}`, in bucket order
+impl<K, V, S, H, O> Debug for HashTable<K, V, S, H, O>
+where
+    K: Debug,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, S, H, O> FromIterator<(K, V)> for HashTable<K, V, S, H, O>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+    H: HashCache,
+    O: KeyOrder<K>,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut hash_table = HashTable::default();
+        hash_table.extend(iter);
+        hash_table
+    }
+}
+
+impl<K, V, S, H, O> Extend<(K, V)> for HashTable<K, V, S, H, O>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+    H: HashCache,
+    O: KeyOrder<K>,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+
+        // Keys may repeat ones already in the table, so only reserve for all of
+        // them when the table is empty
+        let (lower, _) = iter.size_hint();
+        if self.size() == 0 {
+            self.reserve(lower);
+        } else {
+            self.reserve(lower.div_ceil(2));
+        }
+
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<'a, K, V, S, H, O> Extend<&'a (K, V)> for HashTable<K, V, S, H, O>
+where
+    K: Hash + Eq + Copy,
+    V: Copy,
+    S: BuildHasher,
+    H: HashCache,
+    O: KeyOrder<K>,
+{
+    fn extend<I: IntoIterator<Item = &'a (K, V)>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<K, V, const N: usize> From<[(K, V); N]> for HashTable<K, V, RandomState>
+where
+    K: Hash + Eq,
+{
+    fn from(entries: [(K, V); N]) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl<K, Q, V, S, H, O> Index<&Q> for HashTable<K, V, S, H, O>
+where
+    K: Borrow<Q> + Hash + Eq,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
+    H: HashCache,
+    O: KeyOrder<K> + KeyOrder<K, Q>,
+{
+    type Output = V;
+
+    // Panics if the key isn't in the table
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found in HashTable")
+    }
+}
+
+// Same entries, regardless of bucket layout or insertion order
+impl<K, V, S, H, O> PartialEq for HashTable<K, V, S, H, O>
+where
+    K: Hash + Eq,
+    V: PartialEq,
+    S: BuildHasher,
+    H: HashCache,
+    O: KeyOrder<K>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.size() == other.size()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K, V, S, H, O> Eq for HashTable<K, V, S, H, O>
+where
+    K: Hash + Eq,
+    V: Eq,
+    S: BuildHasher,
+    H: HashCache,
+    O: KeyOrder<K>,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_and_collect() {
+        let hash_table = HashTable::from([(1, "one"), (2, "two")]);
+
+        assert_eq!(hash_table.size(), 2);
+        assert_eq!(hash_table[&1], "one");
+
+        let collected: HashTable<u64, u64> = (0..1000).map(|i| (i, i * i)).collect();
+
+        assert_eq!(collected.size(), 1000);
+        assert_eq!(collected[&30], 900);
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut hash_table: HashTable<u64, u64> = HashTable::default();
+
+        hash_table.extend(vec![(1, 1), (2, 2)]);
+        hash_table.extend(&[(2, 20), (3, 30)]);
+
+        assert_eq!(hash_table, HashTable::from([(1, 1), (2, 20), (3, 30)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "key not found")]
+    fn test_index_missing_key() {
+        let hash_table: HashTable<String, u64> = HashTable::from([("key1".to_string(), 1)]);
+        let _ = hash_table["key2"];
+    }
+
+    #[test]
+    fn test_eq_ignores_order() {
+        let forward: HashTable<u64, u64> = (0..100).map(|i| (i, i)).collect();
+        let mut backward: HashTable<u64, u64> = HashTable::new(3);
+        backward.extend((0..100).rev().map(|i| (i, i)));
+
+        assert_eq!(forward, backward);
+
+        backward.insert(50, 0);
+        assert_ne!(forward, backward);
+    }
+
+    #[test]
+    fn test_debug() {
+        let hash_table = HashTable::from([("key1", 1)]);
+        assert_eq!(format!("{:?}", hash_table), r#"{"key1": 1}"#);
+
+        let empty: HashTable<u64, u64> = HashTable::new(10);
+        assert_eq!(format!("{:?}", empty), "{}");
+    }
+}
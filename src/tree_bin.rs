@@ -0,0 +1,156 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+// Chains longer than this get a tree bin...
+pub(crate) const TREEIFY_THRESHOLD: usize = 8;
+// ...and lose it again once they're shorter than this
+pub(crate) const UNTREEIFY_THRESHOLD: usize = 6;
+
+// How a table sorts values sharing a full hash, if it orders their keys at all
+pub(crate) type Order<T> = Option<fn(&T, &T) -> Ordering>;
+
+// Ordered index over one overly long chain, so a bucket that collected lots of
+// colliding keys is still searched in O(log n). The values stay in the chain; this
+// only maps full hashes to chain positions. Keys that share the bucket index but not
+// the full 64-bit hash are told apart without a single `Eq` call, and keys sharing
+// the full hash too are binary searched when the table orders them.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TreeBin {
+    // Hash of the value at each chain position
+    hashes: Vec<u64>,
+    // Chain positions of the values stored under each hash, sorted by key when the
+    // table orders keys. Inserts and removes shift the rest of the run along.
+    runs: BTreeMap<u64, Vec<usize>>,
+}
+
+impl TreeBin {
+    pub(crate) fn new<T>(chain: &[T], hasher: impl Fn(&T) -> u64, order: Order<T>) -> Self {
+        let mut tree_bin = TreeBin::default();
+
+        for value in chain {
+            tree_bin.push(hasher(value), chain, order);
+        }
+
+        tree_bin
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    // Mirrors `Vec::push` on the chain, once the value is pushed
+    pub(crate) fn push<T>(&mut self, hash: u64, chain: &[T], order: Order<T>) {
+        let position = self.hashes.len();
+        let run = self.runs.entry(hash).or_default();
+
+        match order {
+            Some(order) => {
+                let slot = run
+                    .binary_search_by(|&other| order(&chain[other], &chain[position]))
+                    .unwrap_or_else(|slot| slot);
+                run.insert(slot, position);
+            }
+            None => run.push(position),
+        }
+
+        self.hashes.push(hash);
+    }
+
+    // Mirrors `Vec::swap_remove` on the chain, before the value is removed: the last
+    // value moves into `position`
+    pub(crate) fn swap_remove<T>(&mut self, position: usize, chain: &[T], order: Order<T>) {
+        let last = self.hashes.len() - 1;
+
+        let run = self.run_mut(self.hashes[position]);
+        let slot = find_slot(run, position, chain, order);
+        run.remove(slot);
+        if run.is_empty() {
+            self.runs.remove(&self.hashes[position]);
+        }
+
+        // The moved value keeps its key, so its slot in a sorted run stays right
+        if position != last {
+            let run = self.run_mut(self.hashes[last]);
+            let slot = find_slot(run, last, chain, order);
+            run[slot] = position;
+        }
+
+        self.hashes.swap_remove(position);
+    }
+
+    // Chain positions of the values stored under `hash`
+    pub(crate) fn positions(&self, hash: u64) -> &[usize] {
+        self.runs.get(&hash).map_or(&[], Vec::as_slice)
+    }
+
+    fn run_mut(&mut self, hash: u64) -> &mut Vec<usize> {
+        self.runs
+            .get_mut(&hash)
+            .expect("every chain position is in its hash's run")
+    }
+}
+
+// Slot of a chain position in its run: binary searched by key in sorted runs
+fn find_slot<T>(run: &[usize], position: usize, chain: &[T], order: Order<T>) -> usize {
+    let slot = match order {
+        Some(order) => run
+            .binary_search_by(|&other| order(&chain[other], &chain[position]))
+            .ok(),
+        None => run.iter().position(|&other| other == position),
+    };
+
+    slot.expect("every chain position is in its hash's run")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mirrors_chain() {
+        let mut chain: Vec<u64> = vec![5, 7, 5, 9];
+        let mut tree_bin = TreeBin::new(&chain, |&hash| hash, None);
+
+        assert_eq!(tree_bin.positions(5), [0, 2]);
+
+        tree_bin.swap_remove(0, &chain, None);
+        chain.swap_remove(0);
+        chain.push(7);
+        tree_bin.push(7, &chain, None);
+
+        for hash in [5, 7, 9] {
+            for &position in tree_bin.positions(hash) {
+                assert_eq!(chain[position], hash);
+            }
+        }
+
+        assert_eq!(tree_bin.positions(7).len(), 2);
+        assert!(tree_bin.positions(1).is_empty());
+        assert_eq!(tree_bin.len(), chain.len());
+    }
+
+    #[test]
+    fn test_sorted_runs() {
+        // Every key shares hash 0, so they all land in one run sorted by key
+        let mut chain = vec![30, 10, 50, 20, 40];
+        let order: Order<i32> = Some(Ord::cmp);
+        let mut tree_bin = TreeBin::new(&chain, |_| 0, order);
+
+        let assert_sorted = |tree_bin: &TreeBin, chain: &[i32]| {
+            let run: Vec<_> = tree_bin.positions(0).iter().map(|&p| chain[p]).collect();
+            assert!(run.is_sorted());
+            assert_eq!(run.len(), chain.len());
+        };
+        assert_sorted(&tree_bin, &chain);
+
+        tree_bin.swap_remove(1, &chain, order);
+        chain.swap_remove(1);
+        assert_sorted(&tree_bin, &chain);
+
+        chain.push(35);
+        tree_bin.push(0, &chain, order);
+        assert_sorted(&tree_bin, &chain);
+
+        assert_eq!(tree_bin.positions(0), [3, 0, 4, 1, 2]);
+    }
+}